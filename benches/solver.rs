@@ -0,0 +1,159 @@
+/*
+    Rough timings for building the dictionaries and solving a board, plus
+    how much memory each dictionary takes. Run with `cargo bench`.
+ */
+use std::hint::black_box;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+use spellcast_solver::board::Board;
+use spellcast_solver::board_generator::BoardGenerator;
+use spellcast_solver::dawg::Dawg;
+use spellcast_solver::flat_trie::FlatTrie;
+use spellcast_solver::letter::Letter;
+use spellcast_solver::lexicon::Lexicon;
+use spellcast_solver::random::Rng;
+use spellcast_solver::word_tree::{LetterNode, WordTree};
+
+const DICTIONARY: &str = "./data/dictionaries/dictionary.txt";
+const BOARD: &str = "./data/boards/basic_board.txt";
+const BOARDS: [&str; 2] = ["./data/boards/basic_board.txt", "./data/boards/small_board.txt"];
+
+fn time<T>(name: &str, runs: u32, mut f: impl FnMut() -> T) {
+    let mut total = Duration::ZERO;
+
+    for _ in 0..runs {
+        let start = Instant::now();
+        black_box(f());
+        total += start.elapsed();
+    }
+
+    println!("{name:<30} {:>12.3?} per run ({runs} runs)", total / runs);
+}
+
+// (nodes, heap bytes) under this node
+fn tree_size(node: &LetterNode) -> (usize, usize) {
+    let mut nodes = 1;
+    let mut bytes = node.children().capacity() * size_of::<LetterNode>();
+
+    for child in node.children() {
+        let (child_nodes, child_bytes) = tree_size(child);
+        nodes += child_nodes;
+        bytes += child_bytes;
+    }
+
+    (nodes, bytes)
+}
+
+fn solve_all(name: &str, board: &mut Board, lexicon: &(impl Lexicon + Sync)) {
+    for swaps in 0..3 {
+        board.set_swaps(swaps);
+        time(&format!("{name} search, {swaps} swaps"), 5, || board.get_all_possible_words(lexicon));
+        time(&format!("{name} solve, {swaps} swaps"), 5, || board.get_best_words(lexicon, 10));
+    }
+}
+
+/*
+    Searching a board against the full tree and against the tree pruned to
+    that board's letters. The pruned times include building the pruned tree.
+ */
+fn compare_pruned(tree: &WordTree) {
+    for board_file in BOARDS {
+        let mut board = Board::build_board_from_file(board_file).unwrap();
+
+        for swaps in 0..3 {
+            board.set_swaps(swaps);
+            let pruned = board.prune_dictionary(tree);
+            println!("{board_file}, {swaps} swaps: pruned to {} of {} words", pruned.len(), tree.len());
+
+            time("  full tree search", 5, || board.get_all_possible_words(tree));
+            time("  prune + search", 5, || board.get_all_possible_words(&board.prune_dictionary(tree)));
+            time("  pruned search only", 5, || board.get_all_possible_words(&pruned));
+        }
+    }
+}
+
+/*
+    Re-solving a board from a solve cache against solving it from scratch,
+    after changing a few cells along the middle row and down the right
+    edge, the way a word played through the middle of the board would. Each run gets a fresh cache, which
+    isn't counted, since a re-solve moves the cache on to the new board.
+ */
+fn compare_incremental(tree: &WordTree) {
+    let mut board = Board::build_board_from_file(BOARD).unwrap();
+
+    for swaps in 0..3 {
+        board.set_swaps(swaps);
+        time(&format!("{swaps} swaps: full solve"), 5, || board.get_best_words(tree, 10));
+        time(&format!("{swaps} swaps: build cache"), 5, || board.build_solve_cache(tree));
+
+        for changed in 1..=6 {
+            let mut next = board.clone();
+
+            for cell in 0..changed {
+                let middle = board.size() / 2;
+                let (row, col) = if cell < board.size() { (middle, cell) } else { (middle + 1 + cell - board.size(), board.size() - 1) };
+                let old = next.letter_at(row, col).character;
+                let new = if old == 'e' { 'a' } else { 'e' };
+                next.set_letter(row, col, Letter::new(new, next.letter_at(row, col).modifiers()));
+            }
+
+            let runs = 5;
+            let mut total = Duration::ZERO;
+            for _ in 0..runs {
+                let mut cache = board.build_solve_cache(tree);
+                let start = Instant::now();
+                black_box(next.get_best_words_incremental(tree, &mut cache, 10));
+                total += start.elapsed();
+            }
+
+            println!("{:<30} {:>12.3?} per run ({runs} runs)", format!("  {changed} changed cells"), total / runs);
+        }
+    }
+}
+
+// The same handful of random boards every run, so timings compare
+fn solve_generated(tree: &WordTree) {
+    let mut rng = Rng::new(0);
+    let boards: Vec<Board> = (0..10)
+        .map(|round| BoardGenerator::new().for_round(round % 5 + 1).generate(&mut rng).unwrap())
+        .collect();
+
+    for swaps in 0..2 {
+        time(&format!("10 random boards, {swaps} swaps"), 3, || {
+            for board in &boards {
+                let mut board = board.clone();
+                board.set_swaps(swaps);
+                black_box(board.get_best_words(tree, 10));
+            }
+        });
+    }
+}
+
+fn main() {
+    let input = std::fs::read_to_string(DICTIONARY).unwrap();
+    time("build tree", 5, || WordTree::build_from_str(&input));
+    time("build dawg", 5, || Dawg::build_from_str(&input));
+    time("build flat trie", 5, || FlatTrie::build_from_str(&input));
+
+    let binary = WordTree::build_from_str(&input).to_binary(0).unwrap();
+    println!("binary dictionary: {:.1} MiB", binary.len() as f64 / (1024.0 * 1024.0));
+    time("load tree from binary", 5, || WordTree::from_binary(&binary).unwrap());
+
+    let tree = WordTree::build_from_str(&input);
+    let dawg = Dawg::build_from_str(&input);
+    let flat = FlatTrie::from_tree(&tree);
+
+    let (tree_nodes, tree_bytes) = tree_size(tree.get_root_node());
+    println!("tree: {tree_nodes} nodes, {:.1} MiB", tree_bytes as f64 / (1024.0 * 1024.0));
+    println!("dawg: {} nodes, {} edges, {:.1} MiB", dawg.node_count(), dawg.edge_count(), dawg.heap_size() as f64 / (1024.0 * 1024.0));
+    println!("flat trie: {} nodes, {:.1} MiB", flat.node_count(), flat.heap_size() as f64 / (1024.0 * 1024.0));
+
+    let mut board = Board::build_board_from_file(BOARD).unwrap();
+    solve_all("tree", &mut board, &tree);
+    solve_all("dawg", &mut board, &dawg);
+    solve_all("flat trie", &mut board, &flat);
+
+    compare_pruned(&tree);
+    compare_incremental(&tree);
+    solve_generated(&tree);
+}
@@ -0,0 +1,39 @@
+/*
+    With the embedded-dictionary feature on, compiles dictionary.txt into the
+    binary dictionary format so WordTree::default_english can include it.
+ */
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[path = "src/dictionary_file.rs"]
+#[allow(dead_code)]
+mod dictionary_file;
+
+#[path = "src/normalize.rs"]
+#[allow(dead_code)]
+mod normalize;
+
+const DICTIONARY: &str = "data/dictionaries/dictionary.txt";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    if env::var_os("CARGO_FEATURE_EMBEDDED_DICTIONARY").is_none() {
+        return;
+    }
+
+    println!("cargo:rerun-if-changed={DICTIONARY}");
+    println!("cargo:rerun-if-changed=src/dictionary_file.rs");
+    println!("cargo:rerun-if-changed=src/normalize.rs");
+
+    let source = fs::read_to_string(DICTIONARY).unwrap();
+
+    // Same words WordTree::build_from_str would end up with
+    let (words, _) = normalize::NormalizePolicy::default().normalize_word_list(&source).unwrap();
+
+    let bytes = dictionary_file::encode(words.iter().map(|word| (word.as_str(), None)), dictionary_file::hash_source(&source)).unwrap();
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("dictionary.bin"), bytes).unwrap();
+}
@@ -1,18 +1,24 @@
 use std::fmt;
-use crate::{letter::{Letter, Modifier}, word_tree::WordTree};
+use std::collections::HashMap;
+use crate::letter::{Letter, Modifier};
 use std::fs::read_to_string;
-use crate::word_tree::*;
+use crate::lexicon::Lexicon;
+use crate::word_tree::WordTree;
+use crate::language::LanguagePack;
 use crate::letter;
 use crossbeam::{self, thread::ScopedJoinHandle};
 use crate::double_stack::DoubleStack;
 use crate::word_result::{WordResult, Swap, Space};
+use crate::letter_distribution::LetterDistribution;
+use crate::random::Rng;
 
-
+#[derive(Clone)]
 pub struct Board {
     size: usize,
     grid: Vec<Vec<Letter>>,
     swaps: usize,
-    multithreading: bool
+    multithreading: bool,
+    letter_values: HashMap<char, usize>
 }
 
 #[derive(Clone, Copy)]
@@ -23,10 +29,57 @@ pub struct LetterSpace {
     swaps: usize
 }
 
+/*
+    What a previous solve found, kept so that a board which only changed in
+    a few cells can be re-solved by searching just the paths through those
+    cells. Every word that avoids them is still playable for the same points.
+
+    A cache only makes sense with the dictionary it was built with.
+ */
+pub struct SolveCache {
+    grid: Vec<Vec<Letter>>,
+    swaps: usize,
+    words: Vec<(FoundWord, usize)>,
+    steps: Vec<SearchStep>
+}
+
+// One step the search took, pointing back at the step before it
 #[derive(Clone, Copy)]
-pub enum StackElement<'a> {
+struct SearchStep {
+    character: char,
+    row: u32,
+    col: u32,
+    swaps: u32,
+    parent: u32
+}
+
+const NO_PARENT: u32 = u32::MAX;
+
+/*
+    Most changed cells a re-solve picks the cache back up for, by swap
+    count. Past this a full solve is faster, since nearly every path runs
+    through one of them anyway. cargo bench on basic_board.txt, changing
+    cells through the middle of the board, full solve vs re-solve:
+      0 swaps: 0.53ms, 1 cell 0.27ms, 2 cells 0.37ms, 3 cells 0.63ms
+      1 swap:  31-34ms, 1 cell 20ms, 2 cells 24-39ms, 3 cells 41ms
+      2 swaps: 630ms, 1 cell 612ms, 2 cells 843ms
+    Two cells with swaps is too close to call, so it gets a full solve.
+ */
+fn max_incremental_changes(swaps: usize) -> usize {
+    if swaps == 0 { 2 } else { 1 }
+}
+
+// A word the search landed on, with its id if the dictionary has them
+#[derive(Clone)]
+struct FoundWord {
+    spaces: Vec<LetterSpace>,
+    word_id: Option<u32>
+}
+
+#[derive(Clone, Copy)]
+pub enum StackElement<N> {
     LetterStep(LetterSpace),
-    RemoveOp(&'a LetterNode)
+    RemoveOp(N)
 }
 
 impl Board{
@@ -35,35 +88,43 @@ impl Board{
         Board::build_board_from_str(&read_to_string(filename).unwrap())
     }
 
-    // TODO: Remove any panics.
     pub fn build_board_from_str(board: &str) -> Result<Board, String>  {
+        Board::build_board_from_str_in(board, &LanguagePack::english())
+    }
+
+    pub fn build_board_from_file_in(filename: &str, language: &LanguagePack) -> Result<Board, String> {
+        match read_to_string(filename) {
+            Ok(board) => Board::build_board_from_str_in(&board, language),
+            Err(e) => Err(format!("Couldn't read {filename}: {e}"))
+        }
+    }
+
+    // TODO: Remove any panics.
+    pub fn build_board_from_str_in(board: &str, language: &LanguagePack) -> Result<Board, String>  {
         let mut board_vec: Vec<Vec<Letter>> = Vec::new();
         let size = board.lines().count();
 
         for line in board.lines() {
             let mut new_row = Vec::new();
-            for space in line.split_ascii_whitespace() {
-                match Letter::build_letter_from_input_word(space) {
-                    Ok(letter) => {
-                        if !letter::get_letter_value_map().contains_key(&letter.character.to_ascii_lowercase()) {
-                            return Err(String::from("Only English characters are allowed in board input."));
-                        }
-
-                        new_row.push(letter);
-
-                    },
-                    // For now just pretend it's fine unless this causes
-                    // huge issues. On user to notice if it's wrong
-                    Err(_) => ()
+            for space in line.split_whitespace() {
+                // For now just pretend a bad space is fine unless this causes
+                // huge issues. On user to notice if it's wrong
+                if let Ok(mut letter) = Letter::build_letter_from_input_word(space) {
+                    letter.character = match language.normalize_letter(letter.character) {
+                        Some(character) => character,
+                        None => return Err(format!("Only {} letters are allowed in board input.", language.name()))
+                    };
+
+                    new_row.push(letter);
                 }
             }
             board_vec.push(new_row);
         }
 
-        Ok(Board {size, grid: board_vec, swaps: 0, multithreading: false})
+        Ok(Board {size, grid: board_vec, swaps: 0, multithreading: false, letter_values: language.letter_values().clone()})
     }
 
-    pub fn get_longest_word(&self, tree: &WordTree) -> WordResult {
+    pub fn get_longest_word<L: Lexicon + Sync>(&self, tree: &L) -> WordResult {
 
         let words = self.get_all_possible_words(tree);
         let mut longest = String::new();
@@ -71,8 +132,8 @@ impl Board{
 
         for word in &words {
             if word.len() > longest.len() {
-                longest = get_word_from_letter_spaces(&word);
-                longest_cells = &word;
+                longest = get_word_from_letter_spaces(word);
+                longest_cells = word;
             }
         }
 
@@ -83,23 +144,83 @@ impl Board{
         WordResult::new(longest, total, Vec::new(), spaces)
     }
 
-    pub fn get_best_word(&self, tree: &WordTree) -> WordResult {
+    pub fn get_best_word<L: Lexicon + Sync>(&self, tree: &L) -> WordResult {
         let best = (self.get_best_words_spaces(tree, 1)).remove(0);
-        let word: Vec<LetterSpace> = best.0;
 
-        self.get_result_from_letters(word, best.1)
+        self.get_result_with_id(best.0, best.1)
     }
 
-    pub fn get_best_words(&self, tree: &WordTree, count: usize) -> Vec<WordResult> {
+    pub fn get_best_words<L: Lexicon + Sync>(&self, tree: &L, count: usize) -> Vec<WordResult> {
         let words = self.get_best_words_spaces(tree, count);
         let mut results: Vec<WordResult> = Vec::new();
         for word in words {
-            results.push(self.get_result_from_letters(word.0, word.1))
+            results.push(self.get_result_with_id(word.0, word.1))
         }
 
         results
     }
 
+    // get_result_from_letters, plus the id the search found the word with
+    fn get_result_with_id(&self, word: FoundWord, points: usize) -> WordResult {
+        self.get_result_from_letters(word.spaces, points).with_word_id(word.word_id)
+    }
+
+    /*
+        Points for a word played on this board, scored the same way the
+        solver scores the words it finds. Fails unless the spaces are a
+        path on the board, each next to the one before and none used twice,
+        that spells the word once the swaps are made. Spaces and swaps
+        count from 1.
+     */
+    pub fn score_word(&self, result: &WordResult) -> Result<usize, String> {
+        let letters: Vec<char> = result.word().chars().collect();
+
+        if letters.len() != result.spaces().len() {
+            return Err(format!("\"{}\" has {} letters, but {} spaces.", result.word(), letters.len(), result.spaces().len()));
+        }
+
+        for swap in result.swaps() {
+            self.check_space(swap.row(), swap.col())?;
+
+            let original = self.grid[swap.row() - 1][swap.col() - 1].character;
+            if swap.original_char() != original {
+                return Err(format!("The swap at ({}, {}) is from '{}', but the board has '{original}' there.", swap.row(), swap.col(), swap.original_char()));
+            }
+
+            if !result.spaces().iter().any(|space| space.row() == swap.row() && space.col() == swap.col()) {
+                return Err(format!("The swap at ({}, {}) isn't on the word.", swap.row(), swap.col()));
+            }
+        }
+
+        let mut word: Vec<LetterSpace> = Vec::new();
+
+        for (space, &letter) in result.spaces().iter().zip(&letters) {
+            self.check_space(space.row(), space.col())?;
+            let (row, col) = (space.row() - 1, space.col() - 1);
+
+            if word.iter().any(|previous| previous.row == row && previous.col == col) {
+                return Err(format!("({}, {}) is used twice.", space.row(), space.col()));
+            }
+
+            if word.last().is_some_and(|previous| previous.row.abs_diff(row) > 1 || previous.col.abs_diff(col) > 1) {
+                return Err(format!("({}, {}) isn't next to the space before it.", space.row(), space.col()));
+            }
+
+            let character = match result.swaps().iter().find(|swap| swap.row() == space.row() && swap.col() == space.col()) {
+                Some(swap) => swap.new_char(),
+                None => self.grid[row][col].character
+            };
+
+            if character != letter {
+                return Err(format!("\"{}\" needs '{letter}' at ({}, {}), but it's '{character}'.", result.word(), space.row(), space.col()));
+            }
+
+            word.push(LetterSpace { character, row, col, swaps: 0 });
+        }
+
+        Ok(self.get_point_total(&word))
+    }
+
     pub fn get_result_from_letters(&self, word: Vec<LetterSpace>, points: usize) -> WordResult {
         let grid = &self.grid;
         let mut swaps: Vec<Swap> = Vec::new();
@@ -119,25 +240,30 @@ impl Board{
         WordResult::new(get_word_from_letter_spaces(&word), points, swaps, spaces)
     }
 
-    pub fn get_best_word_string(&self, tree: &WordTree) -> (String, usize) {
+    pub fn get_best_word_string<L: Lexicon + Sync>(&self, tree: &L) -> (String, usize) {
         let result = self.get_best_words_spaces(tree, 1).remove(0);
 
-        (get_word_from_letter_spaces(&result.0), result.1)
+        (get_word_from_letter_spaces(&result.0.spaces), result.1)
+    }
+
+    // Points for the best word, or 0 if there's no word on the board at all
+    pub fn best_points<L: Lexicon + Sync>(&self, tree: &L) -> usize {
+        self.get_best_words_spaces(tree, 1).first().map_or(0, |best| best.1)
     }
 
-    fn get_best_words_spaces(&self, tree: &WordTree, count: usize) -> Vec<(Vec<LetterSpace>, usize)> {
+    fn get_best_words_spaces<L: Lexicon + Sync>(&self, tree: &L, count: usize) -> Vec<(FoundWord, usize)> {
         if count == 0 {
             return Vec::new();
         }
 
         let words = if self.multithreading {
-            self.get_all_possible_words_threaded(tree)
+            self.find_all_words_threaded(tree)
         } else {
-            self.get_all_possible_words(tree)
+            self.find_all_words(tree)
         };
 
 
-        let mut words_result: Vec<(Vec<LetterSpace>, usize)> = Vec::with_capacity(count);
+        let mut words_result: Vec<(FoundWord, usize)> = Vec::with_capacity(count.min(words.len()));
 
         // Keep n highest values
         /*
@@ -152,14 +278,14 @@ impl Board{
 
 
         // Fill results
-        for _ in 0..count {
-            match words_iter.next() {
-                Some(word) => {
-                    let points = self.get_point_total(&word);
-                    words_result.push((word, points));
-                }
-                None => ()
-            }
+        for word in words_iter.by_ref().take(count) {
+            let points = self.get_point_total(&word.spaces);
+            words_result.push((word, points));
+        }
+
+        // Boards with no words at all
+        if words_result.is_empty() {
+            return words_result;
         }
 
         let mut min_high_point_total: usize = words_result[0].1;
@@ -173,7 +299,7 @@ impl Board{
         }
 
         for word in words_iter {
-            point_total_temp = self.get_point_total(&word);
+            point_total_temp = self.get_point_total(&word.spaces);
 
             if point_total_temp > min_high_point_total {
                 words_result[min_high_index] = (word, point_total_temp);
@@ -187,36 +313,387 @@ impl Board{
             }
         }
 
-        words_result.sort_by(|a, b| b.1.cmp(&a.1));
+        words_result.sort_by_key(|word| std::cmp::Reverse(word.1));
         words_result
     }
 
 
+    /*
+        Only the words that could be spelled from this board's letters,
+        with up to one letter per swap that isn't on the board. Searching
+        that instead of the full tree finds the same words.
+
+        Building the pruned tree costs about as much as it saves, so this
+        doesn't pay for a single solve and nothing in the solver uses it.
+        It doesn't pay at 0 swaps at all. cargo bench, full tree search vs
+        prune + search vs searching an already pruned tree:
+          basic_board, 0 swaps: 0.75ms, 1.2ms, 0.46ms
+          basic_board, 1 swap:  34ms, 34ms, 32ms
+          basic_board, 2 swaps: 719ms, 609ms, 566ms
+          small_board, 2 swaps: 2.7ms, 4.5ms, 1.4ms
+        It's only worth it when the same board is searched over and over
+        with the same swaps, so the pruning is paid for once.
+     */
+    pub fn prune_dictionary(&self, tree: &WordTree) -> WordTree {
+        let letters: String = self.grid.iter()
+            .flatten()
+            .map(|letter| letter.character)
+            .collect();
+
+        tree.pruned_for_letters(&letters, self.swaps)
+    }
+
+    /*
+        The board once result has been played on it: every cell the word
+        went through gets a new letter drawn from distribution, and loses
+        its modifiers since they've been used up. Cells are refilled in the
+        order the word spelled them, so a given seed always gives the same
+        board. A word with a space off the board is an error.
+     */
+    pub fn after_play(&self, result: &WordResult, distribution: &LetterDistribution, rng: &mut Rng) -> Result<Board, String> {
+        self.check_spaces(result)?;
+
+        Ok(self.refill(result, distribution, rng))
+    }
+
+    /*
+        Gem tiles the word goes through. Like refill, only for words this
+        board's own search found, see game::count_gems for any others.
+     */
+    pub(crate) fn gems_on(&self, result: &WordResult) -> usize {
+        // Spaces count from 1
+        result.spaces().iter()
+            .filter(|space| self.grid[space.row() - 1][space.col() - 1].modifiers().contains(&Modifier::Gem))
+            .count()
+    }
+
+    /*
+        after_play without the check, for words this board's own search
+        found, which never leave the board.
+     */
+    pub(crate) fn refill(&self, result: &WordResult, distribution: &LetterDistribution, rng: &mut Rng) -> Board {
+        let mut board = self.clone();
+
+        // Spaces count from 1
+        for space in result.spaces() {
+            board.set_letter(space.row() - 1, space.col() - 1, Letter::new(distribution.sample(rng), &[]));
+        }
+
+        board
+    }
+
+    // The same letters in a random order, with every modifier left where it was
+    pub fn shuffled(&self, rng: &mut Rng) -> Board {
+        let mut board = self.clone();
+        let mut letters: Vec<char> = self.grid.iter()
+            .flatten()
+            .map(|letter| letter.character)
+            .collect();
+
+        // Fisher-Yates
+        for i in (1..letters.len()).rev() {
+            let j = rng.below(i as u64 + 1) as usize;
+            letters.swap(i, j);
+        }
+
+        for (letter, character) in board.grid.iter_mut().flatten().zip(letters) {
+            letter.character = character;
+        }
+
+        board
+    }
+
+    // The board in the format board files use, so it reads back the same
+    pub fn to_input_string(&self) -> String {
+        let mut text = String::new();
+
+        for row in &self.grid {
+            let spaces: Vec<String> = row.iter()
+                .map(|letter| {
+                    let mut space = letter.character.to_string();
+
+                    for modifier in letter.modifiers() {
+                        match modifier {
+                            Modifier::DoubleLetter => space.push_str("|dl"),
+                            Modifier::TripleLetter => space.push_str("|tl"),
+                            Modifier::DoubleWord => space.push_str("|dw"),
+                            Modifier::Gem => space.push_str("|g"),
+                            Modifier::Default => ()
+                        }
+                    }
+
+                    space
+                })
+                .collect();
+
+            text.push_str(&spaces.join(" "));
+            text.push('\n');
+        }
+
+        text
+    }
+
+    pub fn write_to_file(&self, filename: &str) -> Result<(), String> {
+        match std::fs::write(filename, self.to_input_string()) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("Couldn't write {filename}: {e}"))
+        }
+    }
+
     pub fn set_swaps(&mut self, swaps: usize) {
         self.swaps = swaps;
     }
 
+    pub fn swaps(&self) -> usize {
+        self.swaps
+    }
+
     pub fn set_multithreading(&mut self, use_mt: bool) {
         self.multithreading = use_mt;
     }
 
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // Spaces and swaps count from 1, so (1, 1) up to (size, size) are on the board
+    pub fn check_space(&self, row: usize, col: usize) -> Result<(), String> {
+        if row == 0 || col == 0 || row > self.size || col > self.size {
+            return Err(format!("({row}, {col}) isn't on a {0}x{0} board.", self.size));
+        }
+
+        Ok(())
+    }
+
+    // Every space of the word is on the board
+    pub fn check_spaces(&self, result: &WordResult) -> Result<(), String> {
+        for space in result.spaces() {
+            self.check_space(space.row(), space.col())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn letter_at(&self, row: usize, col: usize) -> &Letter {
+        &self.grid[row][col]
+    }
+
+    pub fn set_letter(&mut self, row: usize, col: usize, letter: Letter) {
+        self.grid[row][col] = letter;
+    }
+
+    pub fn build_solve_cache<L: Lexicon + Sync>(&self, tree: &L) -> SolveCache {
+        let (words, steps) = if self.multithreading {
+            self.get_logged_words_threaded(tree)
+        } else {
+            self.get_logged_words(tree)
+        };
+
+        let mut cache = SolveCache { grid: self.grid.clone(), swaps: self.swaps, words: Vec::new(), steps };
+        self.add_words_to_cache(&mut cache, words);
+
+        cache
+    }
+
+    /*
+        Re-solve using what the cache remembers about the last board it saw.
+        Words avoiding every changed cell are kept as they are. The search is
+        only picked back up at the paths it took last time that lead into a
+        changed cell, and started fresh from changed cells. The cache is moved
+        on to this board, so it can be passed straight into the next re-solve.
+
+        If the board size or swap count changed, or more cells changed than
+        max_incremental_changes allows, nothing carries over and this falls
+        back to a full solve, rebuilding the cache as it goes. Rebuilding is
+        somewhat slower than a plain full solve (780ms against 630ms at 2
+        swaps), so this only pays when most re-solves change a cell or two.
+     */
+    pub fn get_best_words_incremental<L: Lexicon + Sync>(&self, tree: &L, cache: &mut SolveCache, count: usize) -> Vec<WordResult> {
+        match self.get_changed_cells(cache) {
+            Some(changed) if count_changed(&changed) <= max_incremental_changes(self.swaps) => self.update_solve_cache(tree, cache, &changed),
+            _ => *cache = self.build_solve_cache(tree)
+        }
+
+        let mut results: Vec<WordResult> = Vec::new();
+        for word in cache.words.iter().take(count) {
+            results.push(self.get_result_with_id(word.0.clone(), word.1));
+        }
+
+        results
+    }
+
+    fn get_changed_cells(&self, cache: &SolveCache) -> Option<Vec<Vec<bool>>> {
+        if cache.swaps != self.swaps || cache.grid.len() != self.grid.len() {
+            return None;
+        }
+
+        let mut changed: Vec<Vec<bool>> = Vec::new();
+
+        for (old_row, new_row) in cache.grid.iter().zip(&self.grid) {
+            if old_row.len() != new_row.len() {
+                return None;
+            }
+
+            changed.push(old_row.iter().zip(new_row).map(|(old, new)| old != new).collect());
+        }
+
+        Some(changed)
+    }
+
+    fn update_solve_cache<L: Lexicon + Sync>(&self, tree: &L, cache: &mut SolveCache, changed: &[Vec<bool>]) {
+        /*
+            Drop every step that went through a changed cell. Parents always
+            come before their children in the log, so one pass is enough to
+            also drop everything below them.
+         */
+        let mut new_ids: Vec<Option<u32>> = Vec::with_capacity(cache.steps.len());
+        let mut steps: Vec<SearchStep> = Vec::new();
+
+        // Node each kept step ended up on, so picking one back up is cheap
+        let mut nodes: Vec<L::Node<'_>> = Vec::new();
+
+        for step in &cache.steps {
+            let parent = if step.parent == NO_PARENT {
+                Some(NO_PARENT)
+            } else {
+                new_ids[step.parent as usize]
+            };
+
+            match parent {
+                Some(parent) if !changed[step.row as usize][step.col as usize] => {
+                    let parent_node = if parent == NO_PARENT {
+                        tree.root()
+                    } else {
+                        nodes[parent as usize]
+                    };
+
+                    new_ids.push(Some(steps.len() as u32));
+                    steps.push(SearchStep { parent, ..*step });
+                    nodes.push(tree.child(parent_node, step.character).unwrap());
+                },
+                _ => new_ids.push(None)
+            }
+        }
+
+        cache.words.retain(|(word, _)| !touches_changed_cell(&word.spaces, changed));
+
+        let mut words: Vec<FoundWord> = Vec::new();
+
+        // Every path that went through a changed cell either starts on it...
+        for (i, changed_row) in changed.iter().enumerate() {
+            for (j, is_changed) in changed_row.iter().enumerate() {
+                if *is_changed {
+                    words.append(&mut self.get_all_words_from_pos(tree, i, j, self.swaps, Some(&mut steps)));
+                }
+            }
+        }
+
+        // ...or steps into it from a path we already took last time
+        for (id, node) in nodes.into_iter().enumerate() {
+            let step = steps[id];
+
+            for (row, col) in self.get_neighbors(step.row as usize, step.col as usize) {
+                if !changed[row][col] {
+                    continue;
+                }
+
+                words.append(&mut self.get_all_words_from_path(tree, &mut steps, id as u32, node, (row, col)));
+            }
+        }
+
+        cache.steps = steps;
+        cache.grid = self.grid.clone();
+        self.add_words_to_cache(cache, words);
+    }
+
+    fn add_words_to_cache(&self, cache: &mut SolveCache, words: Vec<FoundWord>) {
+        for word in words {
+            let points = self.get_point_total(&word.spaces);
+            cache.words.push((word, points));
+        }
+
+        cache.words.sort_by_key(|word| std::cmp::Reverse(word.1));
+    }
+
+    fn get_logged_words<L: Lexicon + Sync>(&self, tree: &L) -> (Vec<FoundWord>, Vec<SearchStep>) {
+        let mut word_list: Vec<FoundWord> = Vec::new();
+        let mut steps: Vec<SearchStep> = Vec::new();
+
+        for i in 0..self.grid.len() {
+            for j in 0..self.grid[i].len() {
+                word_list.append(&mut self.get_all_words_from_pos(tree, i, j, self.swaps, Some(&mut steps)));
+            }
+        }
+
+        (word_list, steps)
+    }
+
+    /*
+        Same as get_logged_words, but every thread has its own log, so the
+        parent ids in each need shifting over when they're put together.
+     */
+    fn get_logged_words_threaded<L: Lexicon + Sync>(&self, tree: &L) -> (Vec<FoundWord>, Vec<SearchStep>) {
+        let mut word_list: Vec<FoundWord> = Vec::new();
+        let mut steps: Vec<SearchStep> = Vec::new();
+
+        crossbeam::scope(|scope| {
+            let mut handles = Vec::new();
+
+            for i in 0..self.grid.len() {
+                for j in 0..self.grid[i].len() {
+                    handles.push(scope.spawn(move |_| {
+                        let mut log: Vec<SearchStep> = Vec::new();
+                        let words = self.get_all_words_from_pos(tree, i, j, self.swaps, Some(&mut log));
+                        (words, log)
+                    }));
+                }
+            }
+
+            for handle in handles {
+                let (mut words, log) = handle.join().unwrap();
+                let offset = steps.len() as u32;
+
+                word_list.append(&mut words);
+                for step in log {
+                    let parent = if step.parent == NO_PARENT { NO_PARENT } else { step.parent + offset };
+                    steps.push(SearchStep { parent, ..step });
+                }
+            }
+        }).unwrap();
+
+        (word_list, steps)
+    }
+
+    fn get_neighbors(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let mut neighbors: Vec<(usize, usize)> = Vec::new();
+
+        for new_row in row.saturating_sub(1)..=(row + 1).min(self.size - 1) {
+            for new_col in col.saturating_sub(1)..=(col + 1).min(self.size - 1) {
+                if new_row != row || new_col != col {
+                    neighbors.push((new_row, new_col));
+                }
+            }
+        }
+
+        neighbors
+    }
+
     fn get_point_total(&self, word: &Vec<LetterSpace>) -> usize {
-        let letter_score_map = letter::get_letter_value_map();
         let mut points: usize = 0;
         let mut double_word = false;
     
         for letter in word {
-            let mut to_add = *letter_score_map.get(&letter.character.to_ascii_lowercase()).unwrap();
+            let mut to_add = *self.letter_values.get(&letter.character).unwrap_or(&0);
 
             let grid_letter = &self.grid[letter.row()][letter.col()];
             for modifier in grid_letter.modifiers() {
                 match modifier {
                     Modifier::DoubleLetter => {
-                        to_add = to_add * 2;
+                        to_add *= 2;
                     },
 
                     Modifier::TripleLetter => {
-                        to_add = to_add * 3;
+                        to_add *= 3;
                     }
 
                     Modifier::DoubleWord => {
@@ -235,24 +712,32 @@ impl Board{
         }
 
         // Points for long word are not doubled
-        if word.len() >= 6 {
-            points += 10;
+        if word.len() >= letter::LONG_WORD_LENGTH {
+            points += letter::LONG_WORD_BONUS;
         }
     
         points
     }
 
-    pub fn get_all_possible_words(&self, tree: &WordTree) -> Vec<Vec<LetterSpace>> {
-        let mut word_list: Vec<Vec<LetterSpace>> = Vec::new();
+    pub fn get_all_possible_words<L: Lexicon + Sync>(&self, tree: &L) -> Vec<Vec<LetterSpace>> {
+        self.find_all_words(tree).into_iter().map(|word| word.spaces).collect()
+    }
+
+    pub fn get_all_possible_words_threaded<L: Lexicon + Sync>(&self, tree: &L) -> Vec<Vec<LetterSpace>> {
+        self.find_all_words_threaded(tree).into_iter().map(|word| word.spaces).collect()
+    }
+
+    fn find_all_words<L: Lexicon + Sync>(&self, tree: &L) -> Vec<FoundWord> {
+        let mut word_list: Vec<FoundWord> = Vec::new();
 
 
         for i in 0..self.grid.len() {
             for j in 0..self.grid[i].len() {
-                word_list.append(&mut self.get_all_words_from_pos(tree, i, j, self.swaps));
+                word_list.append(&mut self.get_all_words_from_pos(tree, i, j, self.swaps, None));
             }
         }
 
-        return word_list;
+        word_list
     }
 
     /*
@@ -260,15 +745,15 @@ impl Board{
         and start a traversal through all possible letter combinations.
         The way this works is as follows: 
      */
-    pub fn get_all_possible_words_threaded(&self, tree: &WordTree) -> Vec<Vec<LetterSpace>> {
-        let mut word_list: Vec<Vec<LetterSpace>> = Vec::new();
+    fn find_all_words_threaded<L: Lexicon + Sync>(&self, tree: &L) -> Vec<FoundWord> {
+        let mut word_list: Vec<FoundWord> = Vec::new();
         crossbeam::scope(|scope| {
-            let mut handles: Vec<ScopedJoinHandle<Vec<Vec<LetterSpace>>>> = Vec::new();
+            let mut handles: Vec<ScopedJoinHandle<Vec<FoundWord>>> = Vec::new();
 
             for i in 0..self.grid.len() {
                 for j in 0..self.grid[i].len() {
                     handles.push(scope.spawn(move |_| {
-                        self.get_all_words_from_pos(tree, i, j, self.swaps)
+                        self.get_all_words_from_pos(tree, i, j, self.swaps, None)
                     }));
                 }
             }
@@ -284,22 +769,26 @@ impl Board{
         
         //word_list.append(&mut self.get_all_words_from_pos(tree, 0,  0));
 
-        return word_list;
+        word_list
     }
 
     /*
-        First: simple and slow implementation, use is_word_in_tree to check on all words,
-        then change to tree traversal with stack
+        Traversal with a stack, walking the tree alongside the board so a word
+        is found just by landing on a WordEnd node
 
         This really needs to be refactored
+
+        If given a log, every step the search takes is recorded in it so the
+        search can later be picked back up from any point (see SolveCache).
      */
-    fn get_all_words_from_pos(&self, tree: &WordTree, start_row: usize, start_col: usize, swaps: usize) -> Vec<Vec<LetterSpace>> {
-        let mut stack: DoubleStack<StackElement> = DoubleStack::new();
-        let mut words: Vec<Vec<LetterSpace>> = Vec::new();
-        let mut cur_word_grid: Vec<LetterSpace> = Vec::new();
-        let grid = &self.grid;
-        let mut cur_node = tree.get_root_node();
+    fn get_all_words_from_pos<L: Lexicon + Sync>(&self, tree: &L, start_row: usize, start_col: usize, swaps: usize, log: Option<&mut Vec<SearchStep>>) -> Vec<FoundWord> {
+        let mut stack: DoubleStack<StackElement<L::Node<'_>>> = DoubleStack::new();
+        let cur_node = tree.root();
 
+        // Small or pruned dictionaries might not have a word starting here
+        if tree.child(cur_node, self.grid[start_row][start_col].character).is_none() {
+            return Vec::new();
+        }
 
         stack.push_new_layer(StackElement::RemoveOp(cur_node));
 
@@ -310,6 +799,29 @@ impl Board{
             swaps
         }));
 
+        self.run_search(tree, stack, Vec::new(), cur_node, NO_PARENT, log)
+    }
+
+    /*
+        Continue the search from a step logged earlier, which ended on
+        cur_node, stepping next onto the given cell only.
+     */
+    fn get_all_words_from_path<'a, L: Lexicon>(&self, tree: &'a L, steps: &mut Vec<SearchStep>, path_id: u32, cur_node: L::Node<'a>, next_cell: (usize, usize)) -> Vec<FoundWord> {
+        let mut stack: DoubleStack<StackElement<L::Node<'a>>> = DoubleStack::new();
+        let path = get_path_from_steps(steps, path_id);
+
+        stack.push_new_layer(StackElement::RemoveOp(cur_node));
+        self.push_neighbor_steps(tree, cur_node, &mut stack, &path, path[path.len() - 1], next_cell.0, next_cell.1);
+
+        self.run_search(tree, stack, path, cur_node, path_id, Some(steps))
+    }
+
+    fn run_search<'a, L: Lexicon>(&self, tree: &'a L, mut stack: DoubleStack<StackElement<L::Node<'a>>>, mut cur_word_grid: Vec<LetterSpace>, mut cur_node: L::Node<'a>, path_id: u32, mut log: Option<&mut Vec<SearchStep>>) -> Vec<FoundWord> {
+        let mut words: Vec<FoundWord> = Vec::new();
+
+        // Log ids of the steps in cur_word_grid, only kept up when logging
+        let mut path_ids: Vec<u32> = vec![path_id];
+
         let pos_mods: Vec<isize> = vec![-1, 0, 1];
 
         while !stack.is_empty() {
@@ -330,18 +842,21 @@ impl Board{
                         swaps: cell.swaps()
                     });
 
-                    let temp_word = &get_word_from_letter_spaces(&cur_word_grid);
-
-                    if tree.is_word_in_tree(temp_word) {
-                        words.push(cur_word_grid.clone());
+                    if let Some(log) = log.as_deref_mut() {
+                        log.push(SearchStep::new(cell, path_ids[path_ids.len() - 1]));
+                        path_ids.push((log.len() - 1) as u32);
                     }
 
-                    match cur_node.get_child_from_letter(cell.character) {
+                    match tree.child(cur_node, cell.character) {
                         Some(new_node) => {
                             cur_node = new_node;
                         },
                         None => panic!("This shouldn't happen")
                     }
+
+                    if tree.is_word_end(cur_node) {
+                        words.push(FoundWord { spaces: cur_word_grid.clone(), word_id: tree.word_id(cur_node) });
+                    }
                     
                     let mut frame_flag = false;
 
@@ -369,39 +884,7 @@ impl Board{
                                 frame_flag = true;
                             }
 
-                            match cur_node.get_child_from_letter(grid[new_row][new_col].character) {
-                                Some(_) => (),
-                                None => continue
-                            }
-
-                            
-                            let to_add = StackElement::LetterStep(LetterSpace {
-                                character: grid[new_row][new_col].character,
-                                row: new_row,
-                                col: new_col,
-                                swaps: cell.swaps
-                            });
-
-                            let mut is_in_so_far = false;
-
-                            for letter in &cur_word_grid {
-                                if letter.row == new_row && letter.col == new_col {
-                                    is_in_so_far = true;
-                                }
-                            }
-
-                            /*
-                                Meat of letter swaps. For every neighbor, if we have a swap on this cell, add every child
-                                of the current node to the stack at the position of the neighbor, simulating traversal
-                                as if we made that swap.
-                             */
-                            if cell.swaps > 0 && !is_in_so_far{
-                                add_swap_elements(cur_node, &mut stack, new_row, new_col, cell.swaps - 1);
-                            }
-
-                            if !is_in_so_far {
-                                stack.push_simple(to_add);
-                            }
+                            self.push_neighbor_steps(tree, cur_node, &mut stack, &cur_word_grid, cell, new_row, new_col);
                         }
                     }
                 },
@@ -409,12 +892,51 @@ impl Board{
                 StackElement::RemoveOp(parent) => {
                     cur_word_grid.pop();
                     cur_node = parent;
+
+                    if log.is_some() {
+                        path_ids.pop();
+                    }
                 }
             }
         }
 
         words
     }
+
+    /*
+        Push the steps going from cell onto one of its neighbors: the letter
+        that's there, plus every letter it could be swapped to if we still have
+        swaps left. Swaps don't depend on the letter that's there, so they're
+        tried even when it doesn't continue any word.
+     */
+    #[allow(clippy::too_many_arguments)]
+    fn push_neighbor_steps<'a, L: Lexicon>(&self, tree: &'a L, cur_node: L::Node<'a>, stack: &mut DoubleStack<StackElement<L::Node<'a>>>, cur_word_grid: &[LetterSpace], cell: LetterSpace, new_row: usize, new_col: usize) {
+        let grid = &self.grid;
+
+        let is_in_so_far = cur_word_grid.iter().any(|letter| letter.row == new_row && letter.col == new_col);
+
+        if is_in_so_far {
+            return;
+        }
+
+        /*
+            Meat of letter swaps. For every neighbor, if we have a swap on this cell, add every child
+            of the current node to the stack at the position of the neighbor, simulating traversal
+            as if we made that swap.
+         */
+        if cell.swaps > 0 {
+            add_swap_elements(tree, cur_node, stack, new_row, new_col, cell.swaps - 1);
+        }
+
+        if tree.child(cur_node, grid[new_row][new_col].character).is_some() {
+            stack.push_simple(StackElement::LetterStep(LetterSpace {
+                character: grid[new_row][new_col].character,
+                row: new_row,
+                col: new_col,
+                swaps: cell.swaps
+            }));
+        }
+    }
 }
 
 impl fmt::Display for Board{
@@ -439,6 +961,10 @@ impl fmt::Display for Board{
                             board_str.push_str(&format!("({}) ", "DW"));
                         },
 
+                        Modifier::Gem => {
+                            board_str.push_str(&format!("({}) ", "G"));
+                        },
+
                         Modifier::Default => ()
                     }
                 }
@@ -471,7 +997,28 @@ impl LetterSpace {
     }
 }
 
-impl fmt::Display for StackElement<'_> {
+impl SearchStep {
+    fn new(cell: LetterSpace, parent: u32) -> SearchStep {
+        SearchStep {
+            character: cell.character,
+            row: cell.row as u32,
+            col: cell.col as u32,
+            swaps: cell.swaps as u32,
+            parent
+        }
+    }
+
+    fn letter_space(&self) -> LetterSpace {
+        LetterSpace {
+            character: self.character,
+            row: self.row as usize,
+            col: self.col as usize,
+            swaps: self.swaps as usize
+        }
+    }
+}
+
+impl<N> fmt::Display for StackElement<N> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             StackElement::LetterStep(letter) => write!(f, "[{} {} {}]", letter.character(), letter.row(), letter.col()),
@@ -489,32 +1036,38 @@ fn get_word_from_letter_spaces(letters: &Vec<LetterSpace>) -> String{
     result
 }
 
-#[allow(dead_code)]
-fn get_point_total_str(word: &str) -> usize {
-    let letter_score_map = letter::get_letter_value_map();
-    let mut points: usize = 0;
-
-    for letter in word.chars() {
-        points += letter_score_map.get(&letter).unwrap();
-    }
-
-    points
-}
-
-fn add_swap_elements(node: &LetterNode, stack: &mut DoubleStack<StackElement>, row: usize, col: usize, swaps: usize) {
-    let children = node.children();
-
-    for child in children {
+fn add_swap_elements<'a, L: Lexicon>(tree: &'a L, node: L::Node<'a>, stack: &mut DoubleStack<StackElement<L::Node<'a>>>, row: usize, col: usize, swaps: usize) {
+    tree.for_each_child(node, |character, _| {
         stack.push_simple(StackElement::LetterStep(LetterSpace {
-            character: match child.letter() {
-                LetterState::Present(character) => character.clone(),
-                LetterState::Root => panic!("This should never happen.")
-            },
+            character,
             row,
             col,
             swaps
         }));
+    });
+}
+
+fn count_changed(changed: &[Vec<bool>]) -> usize {
+    changed.iter().flatten().filter(|&&is_changed| is_changed).count()
+}
+
+fn touches_changed_cell(word: &[LetterSpace], changed: &[Vec<bool>]) -> bool {
+    word.iter().any(|letter| changed[letter.row][letter.col])
+}
+
+// Follow the parents back up to rebuild the whole path ending at this step
+fn get_path_from_steps(steps: &[SearchStep], id: u32) -> Vec<LetterSpace> {
+    let mut path: Vec<LetterSpace> = Vec::new();
+    let mut cur = id;
+
+    while cur != NO_PARENT {
+        let step = steps[cur as usize];
+        path.push(step.letter_space());
+        cur = step.parent;
     }
+
+    path.reverse();
+    path
 }
 
 fn get_letter_spaces_for_word(word: &Vec<LetterSpace>) -> Vec<Space>{
@@ -526,3 +1079,125 @@ fn get_letter_spaces_for_word(word: &Vec<LetterSpace>) -> Vec<Space>{
 
     spaces
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word_result::word_on_cells;
+
+    fn get_sorted_results(results: Vec<WordResult>) -> Vec<(String, usize)> {
+        let mut words: Vec<(String, usize)> = results
+            .iter()
+            .map(|result| (result.word().to_string(), result.points()))
+            .collect();
+
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn test_incremental_matches_full_solve(){
+        let tree = WordTree::build_from_file("./data/dictionaries/dictionary.txt");
+        let mut board = Board::build_board_from_file("./data/boards/basic_board.txt").unwrap();
+        board.set_swaps(1);
+        let mut cache = board.build_solve_cache(&tree);
+
+        board.set_letter(0, 1, Letter::new('s', &[]));
+        board.set_letter(2, 2, Letter::new('r', &[Modifier::DoubleWord]));
+
+        let incremental = get_sorted_results(board.get_best_words_incremental(&tree, &mut cache, usize::MAX));
+        assert_eq!(incremental, get_sorted_results(board.get_best_words(&tree, usize::MAX)));
+
+        // Second change on top of the first, overlapping one of its cells
+        board.set_letter(2, 2, Letter::new('a', &[]));
+        board.set_letter(2, 3, Letter::new('q', &[]));
+
+        let incremental = get_sorted_results(board.get_best_words_incremental(&tree, &mut cache, usize::MAX));
+        assert_eq!(incremental, get_sorted_results(board.get_best_words(&tree, usize::MAX)));
+
+        // Too many changes to pick back up, so this is a full solve
+        board.set_letter(0, 0, Letter::new('t', &[]));
+        board.set_letter(1, 1, Letter::new('o', &[]));
+        board.set_letter(4, 4, Letter::new('t', &[]));
+
+        let incremental = get_sorted_results(board.get_best_words_incremental(&tree, &mut cache, usize::MAX));
+        assert_eq!(incremental, get_sorted_results(board.get_best_words(&tree, usize::MAX)));
+    }
+
+    #[test]
+    fn test_pruned_dictionary_finds_same_words(){
+        let tree = WordTree::build_from_file("./data/dictionaries/dictionary.txt");
+        let mut board = Board::build_board_from_file("./data/boards/basic_board.txt").unwrap();
+
+        for swaps in 0..2 {
+            board.set_swaps(swaps);
+            let pruned = board.prune_dictionary(&tree);
+
+            assert!(pruned.node_count() < tree.node_count());
+            assert_eq!(get_sorted_results(board.get_best_words(&pruned, usize::MAX)), get_sorted_results(board.get_best_words(&tree, usize::MAX)));
+        }
+    }
+
+    // Best score for every word found, as "word points" lines sorted by word
+    /*
+        Neither x continues "c", but either one can be swapped to the a that
+        does, so swaps have to be tried whatever letter is there already.
+     */
+    #[test]
+    fn test_swaps_on_every_neighbor(){
+        let tree = WordTree::build_from_str("cat");
+        let mut board = Board::build_board_from_str("c x\nt x").unwrap();
+        assert!(board.get_best_words(&tree, 1).is_empty());
+
+        board.set_swaps(1);
+        let results = board.get_best_words(&tree, usize::MAX);
+        assert_eq!(results.len(), 2);
+
+        for result in &results {
+            assert_eq!(result.word(), "cat");
+            assert_eq!(result.swaps().len(), 1);
+            assert_eq!((result.swaps()[0].original_char(), result.swaps()[0].new_char()), ('x', 'a'));
+        }
+    }
+
+    #[test]
+    fn test_refill_after_play(){
+        let board = Board::build_board_from_str("c|dl a\nt s|g").unwrap();
+        let cat = word_on_cells("cat", 6, &[(1, 1), (1, 2), (2, 1)], Vec::new());
+        let distribution = LetterDistribution::spellcast();
+
+        let after = board.after_play(&cat, &distribution, &mut Rng::new(11)).unwrap();
+        assert_eq!(after.to_string(), board.after_play(&cat, &distribution, &mut Rng::new(11)).unwrap().to_string());
+        assert_eq!(after.to_string(), " F E\n O S(G) \n");
+
+        // The cell the word didn't use is left alone
+        assert!(after.letter_at(1, 1) == board.letter_at(1, 1));
+        assert!(after.letter_at(0, 0).modifiers().is_empty());
+
+        // Spaces count from 1, so neither of these is on a 2x2 board
+        let off_board = word_on_cells("at", 2, &[(0, 2), (2, 3)], Vec::new());
+        assert!(board.after_play(&off_board, &distribution, &mut Rng::new(11)).is_err());
+    }
+    #[test]
+    fn test_score_played_words(){
+        let tree = WordTree::build_from_file("./data/dictionaries/dictionary.txt");
+        let mut board = Board::build_board_from_file("./data/boards/basic_board.txt").unwrap();
+        board.set_swaps(1);
+
+        // Scores the solver's own words the way it did
+        for result in board.get_best_words(&tree, 50) {
+            assert_eq!(board.score_word(&result), Ok(result.points()), "{}", result.word());
+        }
+
+        let small = Board::build_board_from_str("c|dl a\nt s").unwrap();
+        assert_eq!(small.score_word(&word_on_cells("cat", 0, &[(1, 1), (1, 2), (2, 1)], Vec::new())), Ok(13));
+        assert_eq!(small.score_word(&word_on_cells("bat", 0, &[(1, 1), (1, 2), (2, 1)], vec![Swap::new('c', 'b', 1, 1)])), Ok(11));
+
+        // Misspelled, off the board, a cell twice, and a swap from the wrong letter
+        assert!(small.score_word(&word_on_cells("cot", 0, &[(1, 1), (1, 2), (2, 1)], Vec::new())).is_err());
+        assert!(small.score_word(&word_on_cells("cat", 0, &[(1, 1), (1, 2), (3, 1)], Vec::new())).is_err());
+        assert!(small.score_word(&word_on_cells("aca", 0, &[(1, 2), (1, 1), (1, 2)], Vec::new())).is_err());
+        assert!(small.score_word(&word_on_cells("bat", 0, &[(1, 1), (1, 2), (2, 1)], vec![Swap::new('x', 'b', 1, 1)])).is_err());
+    }
+}
+
@@ -0,0 +1,152 @@
+use crate::board::Board;
+use crate::game::DOUBLE_WORD_FROM_ROUND;
+use crate::letter_distribution::LetterDistribution;
+use crate::random::Rng;
+
+pub const BOARD_SIZE: usize = 5;
+pub const DEFAULT_GEMS: usize = 6;
+pub const DEFAULT_TRIPLE_LETTER_CHANCE: f64 = 0.5;
+
+/*
+    Random boards that look like Spellcast's: a 5x5 grid of letters from a
+    letter distribution, one double or triple letter tile, gem tiles, and a
+    double word tile once the round has one. Boards come from a seed, so
+    the same seed always gives the same board.
+
+    generate_text gives the board in the same format board files use, so
+    generated boards can be saved and loaded like any other.
+ */
+#[derive(Clone)]
+pub struct BoardGenerator {
+    size: usize,
+    distribution: LetterDistribution,
+    gems: usize,
+    triple_letter_chance: f64,
+    double_word: bool
+}
+
+impl BoardGenerator {
+    // A first round board, so without a double word tile
+    pub fn new() -> BoardGenerator {
+        BoardGenerator {
+            size: BOARD_SIZE,
+            distribution: LetterDistribution::spellcast(),
+            gems: DEFAULT_GEMS,
+            triple_letter_chance: DEFAULT_TRIPLE_LETTER_CHANCE,
+            double_word: false
+        }
+    }
+
+    pub fn with_size(mut self, size: usize) -> BoardGenerator {
+        self.size = size;
+        self
+    }
+
+    pub fn with_distribution(mut self, distribution: LetterDistribution) -> BoardGenerator {
+        self.distribution = distribution;
+        self
+    }
+
+    pub fn with_gems(mut self, gems: usize) -> BoardGenerator {
+        self.gems = gems;
+        self
+    }
+
+    // Chance the letter multiplier is a triple instead of a double
+    pub fn with_triple_letter_chance(mut self, chance: f64) -> BoardGenerator {
+        self.triple_letter_chance = chance;
+        self
+    }
+
+    pub fn with_double_word(mut self, double_word: bool) -> BoardGenerator {
+        self.double_word = double_word;
+        self
+    }
+
+    // Double word tile if the round would have one, counting rounds from 1
+    pub fn for_round(self, round: usize) -> BoardGenerator {
+        self.with_double_word(round >= DOUBLE_WORD_FROM_ROUND)
+    }
+
+    pub fn generate(&self, rng: &mut Rng) -> Result<Board, String> {
+        Board::build_board_from_str(&self.generate_text(rng))
+    }
+
+    pub fn generate_text(&self, rng: &mut Rng) -> String {
+        let cells = self.size * self.size;
+        let mut modifiers: Vec<String> = vec![String::new(); cells];
+
+        // The letter and word multipliers never share a cell
+        let multipliers = pick_cells(cells, if self.double_word { 2 } else { 1 }, rng);
+        if let Some(&cell) = multipliers.first() {
+            let triple = rng.next_f64() < self.triple_letter_chance;
+            modifiers[cell].push_str(if triple { "|tl" } else { "|dl" });
+        }
+        if let Some(&cell) = multipliers.get(1) {
+            modifiers[cell].push_str("|dw");
+        }
+
+        for cell in pick_cells(cells, self.gems, rng) {
+            modifiers[cell].push_str("|g");
+        }
+
+        let mut text = String::new();
+        for row in 0..self.size {
+            let spaces: Vec<String> = (0..self.size)
+                .map(|col| format!("{}{}", self.distribution.sample(rng), modifiers[row * self.size + col]))
+                .collect();
+
+            text.push_str(&spaces.join(" "));
+            text.push('\n');
+        }
+
+        text
+    }
+}
+
+impl Default for BoardGenerator {
+    fn default() -> BoardGenerator {
+        BoardGenerator::new()
+    }
+}
+
+// count different cells out of cells, or all of them if there aren't enough
+fn pick_cells(cells: usize, count: usize, rng: &mut Rng) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..cells).collect();
+    let count = count.min(cells);
+
+    // The first count steps of a Fisher-Yates shuffle
+    for i in 0..count {
+        let j = i + rng.below((cells - i) as u64) as usize;
+        order.swap(i, j);
+    }
+
+    order.truncate(count);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::count_modifier;
+    use crate::letter::Modifier;
+
+    #[test]
+    fn test_generated_boards(){
+        let generator = BoardGenerator::new().for_round(3);
+        let text = generator.generate_text(&mut Rng::new(4));
+        assert_eq!(text, generator.generate_text(&mut Rng::new(4)));
+        assert_ne!(text, generator.generate_text(&mut Rng::new(5)));
+
+        let board = Board::build_board_from_str(&text).unwrap();
+        assert_eq!(board.size(), BOARD_SIZE);
+        assert_eq!(board.to_input_string(), text);
+        assert_eq!(count_modifier(&board, &Modifier::Gem), DEFAULT_GEMS);
+        assert_eq!(count_modifier(&board, &Modifier::DoubleWord), 1);
+        assert_eq!(count_modifier(&board, &Modifier::DoubleLetter) + count_modifier(&board, &Modifier::TripleLetter), 1);
+
+        let first_round = BoardGenerator::new().with_size(3).with_gems(20).generate(&mut Rng::new(4)).unwrap();
+        assert_eq!(count_modifier(&first_round, &Modifier::DoubleWord), 0);
+        assert_eq!(count_modifier(&first_round, &Modifier::Gem), 9);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::lexicon::Lexicon;
+
+/*
+    A dictionary with the words known to be uncommon left out: any word
+    with a frequency under min_frequency stops counting as a word. Words
+    we don't have a frequency for are kept, since there's no telling.
+
+    Board searches can use it like any other dictionary, so the solver
+    only suggests words players will recognise.
+ */
+pub struct CommonWords<'a, L: Lexicon> {
+    lexicon: &'a L,
+    min_frequency: u32
+}
+
+impl<'a, L: Lexicon> CommonWords<'a, L> {
+    pub fn new(lexicon: &'a L, min_frequency: u32) -> CommonWords<'a, L> {
+        CommonWords { lexicon, min_frequency }
+    }
+
+    pub fn min_frequency(&self) -> u32 {
+        self.min_frequency
+    }
+
+    // None if the word isn't in the dictionary or has no frequency
+    pub fn is_common(&self, word: &str) -> Option<bool> {
+        self.lexicon.word_frequency(word).map(|frequency| frequency >= self.min_frequency)
+    }
+}
+
+impl<'b, L: Lexicon> Lexicon for CommonWords<'b, L> {
+    type Node<'a> = L::Node<'a> where Self: 'a;
+
+    fn root(&self) -> L::Node<'_> {
+        self.lexicon.root()
+    }
+
+    fn child<'a>(&'a self, node: L::Node<'a>, character: char) -> Option<L::Node<'a>> {
+        self.lexicon.child(node, character)
+    }
+
+    fn is_word_end<'a>(&'a self, node: L::Node<'a>) -> bool {
+        if !self.lexicon.is_word_end(node) {
+            return false;
+        }
+
+        match self.lexicon.frequency(node) {
+            Some(frequency) => frequency >= self.min_frequency,
+            None => true
+        }
+    }
+
+    fn for_each_child<'a>(&'a self, node: L::Node<'a>, f: impl FnMut(char, Self::Node<'a>)) {
+        self.lexicon.for_each_child(node, f);
+    }
+
+    fn frequency<'a>(&'a self, node: L::Node<'a>) -> Option<u32> {
+        self.lexicon.frequency(node)
+    }
+
+    fn word_id<'a>(&'a self, node: L::Node<'a>) -> Option<u32> {
+        if !self.is_word_end(node) {
+            return None;
+        }
+
+        self.lexicon.word_id(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word_tree::WordTree;
+    use crate::layered_dictionary::LayeredDictionary;
+
+    #[test]
+    fn test_uncommon_words_left_out(){
+        let mut tree = WordTree::build_from_str("the\nthee\nthey\nzyzzyva");
+        tree.load_frequencies_from_str("the\t5000\nthee\t20\nthey\t3000").unwrap();
+
+        let common = CommonWords::new(&tree, 1000);
+        assert!(common.is_word("the"));
+        assert!(common.is_word("they"));
+        assert!(!common.is_word("thee"));
+        assert!(common.is_word("zyzzyva"));
+
+        assert_eq!(common.is_common("thee"), Some(false));
+        assert_eq!(common.is_common("they"), Some(true));
+        assert_eq!(common.is_common("zyzzyva"), None);
+
+        // Frequencies come through the layers too
+        let mut layered = LayeredDictionary::new(tree);
+        layered.deny_word("the").unwrap();
+        let common = CommonWords::new(&layered, 1000);
+        assert!(!common.is_word("the"));
+        assert!(!common.is_word("thee"));
+        assert!(common.is_word("they"));
+    }
+}
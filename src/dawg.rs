@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use crate::lexicon::Lexicon;
+use crate::letter::get_letter_slot;
+use crate::normalize::NormalizePolicy;
+
+/*
+    A minimized DAWG (directed acyclic word graph) holding the same words a
+    WordTree would. It's a trie where identical subtrees, which are mostly
+    shared word endings like "-ing" or "-ness", are only stored once.
+
+    Nodes and edges live in two flat Vecs and refer to each other by index.
+    A node's edges are laid out like LetterNode's children: slotted letters
+    first in slot order (with child_mask marking them), anything else after.
+ */
+pub struct Dawg {
+    nodes: Vec<DawgNode>,
+    edges: Vec<DawgEdge>,
+    word_count: usize
+}
+
+#[derive(Clone, Copy)]
+struct DawgNode {
+    first_edge: u32,
+    edge_count: u32,
+    child_mask: u32,
+    word_end: bool
+}
+
+#[derive(Clone, Copy)]
+struct DawgEdge {
+    character: char,
+    target: u32
+}
+
+/*
+    Node while the DAWG is being built. Two of these with the same word_end
+    and the same edges (pointing at already minimized nodes) are the same
+    subtree, so this doubles as the key for the register.
+ */
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct BuildNode {
+    word_end: bool,
+    edges: Vec<(char, u32)>
+}
+
+/*
+    Builds the DAWG one word at a time from sorted input (Daciuk et al.).
+    Only the path of the last word added can still change, so whenever the
+    next word branches off it, everything below the branch point is final
+    and gets merged with an identical node already registered, if any.
+ */
+struct DawgBuilder {
+    nodes: Vec<BuildNode>,
+    register: HashMap<BuildNode, u32>,
+    // (parent, letter, child) along the last word, not yet minimized
+    unchecked: Vec<(u32, char, u32)>,
+    previous: Vec<char>
+}
+
+impl Dawg {
+    pub fn build_from_file(file_name: &str) -> Dawg {
+        Dawg::build_from_str(&read_to_string(file_name).unwrap())
+    }
+
+    // Same words WordTree::build_from_str would end up with
+    pub fn build_from_str(input: &str) -> Dawg {
+        let words = match NormalizePolicy::default().normalize_word_list(input) {
+            Ok((words, _)) => words,
+            Err(e) => panic!("Skipping invalid words shouldn't fail: {e}")
+        };
+
+        let mut builder = DawgBuilder::new();
+
+        for word in &words {
+            builder.add_word(word);
+        }
+
+        builder.finish(words.len())
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.word_count
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    // Bytes held on the heap by the node and edge arrays
+    pub fn heap_size(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<DawgNode>()
+            + self.edges.capacity() * std::mem::size_of::<DawgEdge>()
+    }
+
+    fn get_edges(&self, node: u32) -> &[DawgEdge] {
+        let node = &self.nodes[node as usize];
+        let start = node.first_edge as usize;
+
+        &self.edges[start..start + node.edge_count as usize]
+    }
+}
+
+impl Lexicon for Dawg {
+    type Node<'a> = u32;
+
+    fn root(&self) -> u32 {
+        0
+    }
+
+    fn child(&self, node: u32, character: char) -> Option<u32> {
+        let child_mask = self.nodes[node as usize].child_mask;
+        let edges = self.get_edges(node);
+
+        match get_letter_slot(character) {
+            Some(slot) => {
+                if child_mask & (1 << slot) == 0 {
+                    return None;
+                }
+
+                Some(edges[(child_mask & ((1 << slot) - 1)).count_ones() as usize].target)
+            },
+            None => {
+                let slotted = child_mask.count_ones() as usize;
+
+                edges[slotted..].iter()
+                    .find(|edge| edge.character == character)
+                    .map(|edge| edge.target)
+            }
+        }
+    }
+
+    fn is_word_end(&self, node: u32) -> bool {
+        self.nodes[node as usize].word_end
+    }
+
+    fn for_each_child<'a>(&'a self, node: u32, mut f: impl FnMut(char, Self::Node<'a>)) {
+        for edge in self.get_edges(node) {
+            f(edge.character, edge.target);
+        }
+    }
+}
+
+impl DawgBuilder {
+    fn new() -> DawgBuilder {
+        DawgBuilder {
+            nodes: vec![BuildNode { word_end: false, edges: Vec::new() }],
+            register: HashMap::new(),
+            unchecked: Vec::new(),
+            previous: Vec::new()
+        }
+    }
+
+    // Words have to come in sorted order, without repeats
+    fn add_word(&mut self, word: &str) {
+        let chars: Vec<char> = word.chars().collect();
+
+        let common = chars.iter()
+            .zip(&self.previous)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        self.minimize(common);
+
+        let mut node = match self.unchecked.last() {
+            Some(&(_, _, child)) => child,
+            None => 0
+        };
+
+        for &character in &chars[common..] {
+            let child = self.nodes.len() as u32;
+            self.nodes.push(BuildNode { word_end: false, edges: Vec::new() });
+            self.nodes[node as usize].edges.push((character, child));
+            self.unchecked.push((node, character, child));
+            node = child;
+        }
+
+        self.nodes[node as usize].word_end = true;
+        self.previous = chars;
+    }
+
+    fn minimize(&mut self, down_to: usize) {
+        while self.unchecked.len() > down_to {
+            let (parent, _, child) = self.unchecked.pop().unwrap();
+            let key = self.nodes[child as usize].clone();
+
+            match self.register.get(&key) {
+                Some(&existing) => {
+                    // The child is always the last edge added to its parent
+                    let edges = &mut self.nodes[parent as usize].edges;
+                    let last = edges.len() - 1;
+                    edges[last].1 = existing;
+                },
+                None => {
+                    self.register.insert(key, child);
+                }
+            }
+        }
+    }
+
+    /*
+        Merged-away nodes are still sitting in the build Vec, so copy out
+        only what's reachable from the root, renumbering as we go.
+     */
+    fn finish(mut self, word_count: usize) -> Dawg {
+        self.minimize(0);
+
+        let mut new_ids: Vec<Option<u32>> = vec![None; self.nodes.len()];
+        let mut order: Vec<u32> = vec![0];
+        new_ids[0] = Some(0);
+
+        let mut i = 0;
+        while i < order.len() {
+            for &(_, child) in &self.nodes[order[i] as usize].edges {
+                if new_ids[child as usize].is_none() {
+                    new_ids[child as usize] = Some(order.len() as u32);
+                    order.push(child);
+                }
+            }
+            i += 1;
+        }
+
+        let mut nodes: Vec<DawgNode> = Vec::with_capacity(order.len());
+        let mut edges: Vec<DawgEdge> = Vec::new();
+
+        for old_id in order {
+            let build_node = &self.nodes[old_id as usize];
+            let mut node_edges: Vec<DawgEdge> = build_node.edges.iter()
+                .map(|&(character, target)| DawgEdge { character, target: new_ids[target as usize].unwrap() })
+                .collect();
+
+            // Slotted letters first in slot order, then everything else
+            node_edges.sort_by_key(|edge| (get_letter_slot(edge.character).unwrap_or(u32::MAX), edge.character));
+
+            let mut child_mask = 0;
+            for edge in &node_edges {
+                if let Some(slot) = get_letter_slot(edge.character) {
+                    child_mask |= 1 << slot;
+                }
+            }
+
+            nodes.push(DawgNode {
+                first_edge: edges.len() as u32,
+                edge_count: node_edges.len() as u32,
+                child_mask,
+                word_end: build_node.word_end
+            });
+            edges.append(&mut node_edges);
+        }
+
+        Dawg { nodes, edges, word_count }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word_tree::WordTree;
+    use crate::board::Board;
+
+    #[test]
+    fn test_words_in_dawg(){
+        let dawg = Dawg::build_from_file("./data/dictionaries/test_1.txt");
+
+        assert!(dawg.is_word("obfuscate"));
+        assert!(dawg.is_word("apple"));
+        assert!(!dawg.is_word("creaturue"));
+        assert!(!dawg.is_word("obf"));
+    }
+
+    #[test]
+    fn test_shared_suffixes(){
+        let dawg = Dawg::build_from_str("walking\ntalking\ntalk\nwalk\ndone\ncafe");
+
+        assert_eq!(dawg.word_count(), 6);
+        assert!(dawg.is_word("talk"));
+        assert!(dawg.is_word("walking"));
+        assert!(dawg.is_word("done"));
+        assert!(dawg.is_word("cafe"));
+        assert!(!dawg.is_word("walkin"));
+
+        // Root, the "alking" chain t and w share (ending on the one leaf
+        // every word shares), then "ca" and "do" on their own before they
+        // join up for the final "e"
+        assert_eq!(dawg.node_count(), 1 + 7 + 2 + 2 + 1);
+    }
+
+    #[test]
+    fn test_same_words_as_tree(){
+        let tree = WordTree::build_from_file("./data/dictionaries/dictionary.txt");
+        let dawg = Dawg::build_from_file("./data/dictionaries/dictionary.txt");
+        let mut board = Board::build_board_from_file("./data/boards/basic_board.txt").unwrap();
+        board.set_swaps(1);
+
+        // Every word, sorted, since the order of equal scores isn't fixed
+        let mut tree_words: Vec<(String, usize)> = board.get_best_words(&tree, usize::MAX).iter()
+            .map(|result| (result.word().to_string(), result.points()))
+            .collect();
+        let mut dawg_words: Vec<(String, usize)> = board.get_best_words(&dawg, usize::MAX).iter()
+            .map(|result| (result.word().to_string(), result.points()))
+            .collect();
+        tree_words.sort();
+        dawg_words.sort();
+
+        assert_eq!(tree_words, dawg_words);
+    }
+}
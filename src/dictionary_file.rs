@@ -0,0 +1,175 @@
+/*
+    Binary dictionary format, so a word list only has to be read and
+    cleaned up once and can then be reloaded quickly.
+
+    Everything is little-endian:
+
+        magic     4 bytes   "SCWT"
+        version   u32       FORMAT_VERSION
+        source    u64       hash_source of the text the words came from
+        count     u32       number of words
+
+    followed by each word, in the order they were written:
+
+        shared    u8        chars in common with the start of the previous word
+        length    u8        byte length of the rest of the word
+        rest      [u8]      the rest of the word, UTF-8
+        counted   u8        1 if a frequency follows, 0 if not
+        frequency u32       the word's frequency, only there if counted is 1
+
+    Writing the words in trie order makes neighbours share long prefixes,
+    so most words cost just a couple of bytes.
+
+    This file doesn't depend on the rest of the crate, so the build script
+    can use it as well.
+ */
+
+pub const MAGIC: &[u8; 4] = b"SCWT";
+pub const FORMAT_VERSION: u32 = 2;
+
+const HEADER_LEN: usize = 20;
+
+/*
+    FNV-1a over the source text. Used to tell whether a saved dictionary is
+    stale, and unlike std's hasher it won't change between Rust versions.
+ */
+pub fn hash_source(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for byte in text.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+// Each word with its frequency, if it has one
+pub fn encode<'a>(words: impl IntoIterator<Item = (&'a str, Option<u32>)>, source_hash: u64) -> Result<Vec<u8>, String> {
+    let mut body: Vec<u8> = Vec::new();
+    let mut previous = "";
+    let mut count: u32 = 0;
+
+    for (word, frequency) in words {
+        let mut shared = 0;
+        let mut shared_bytes = 0;
+
+        for (a, b) in word.chars().zip(previous.chars()) {
+            if a != b || shared == u8::MAX as usize {
+                break;
+            }
+            shared += 1;
+            shared_bytes += a.len_utf8();
+        }
+
+        let rest = &word.as_bytes()[shared_bytes..];
+
+        if rest.len() > u8::MAX as usize {
+            return Err(format!("Word too long for dictionary file: {word}"));
+        }
+
+        body.push(shared as u8);
+        body.push(rest.len() as u8);
+        body.extend_from_slice(rest);
+
+        match frequency {
+            Some(frequency) => {
+                body.push(1);
+                body.extend_from_slice(&frequency.to_le_bytes());
+            },
+            None => body.push(0)
+        }
+
+        previous = word;
+        count += 1;
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(HEADER_LEN + body.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&source_hash.to_le_bytes());
+    bytes.extend_from_slice(&count.to_le_bytes());
+    bytes.append(&mut body);
+
+    Ok(bytes)
+}
+
+// Just the source hash, without decoding any words
+pub fn read_source_hash(bytes: &[u8]) -> Result<u64, String> {
+    check_header(bytes)?;
+
+    Ok(u64::from_le_bytes(bytes[8..16].try_into().unwrap()))
+}
+
+// A word and its frequency, if it has one
+pub type CountedWord = (String, Option<u32>);
+
+// Returns the source hash and the words with their frequencies
+pub fn decode(bytes: &[u8]) -> Result<(u64, Vec<CountedWord>), String> {
+    let mut words: Vec<CountedWord> = Vec::new();
+    let source_hash = decode_each(bytes, |word, frequency| words.push((word.to_string(), frequency)))?;
+
+    Ok((source_hash, words))
+}
+
+/*
+    Calls f with each word and its frequency in turn, without allocating
+    for every one of them. Returns the source hash.
+ */
+pub fn decode_each(bytes: &[u8], mut f: impl FnMut(&str, Option<u32>)) -> Result<u64, String> {
+    let source_hash = read_source_hash(bytes)?;
+    let count = u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize;
+
+    let mut pos = HEADER_LEN;
+    let mut word = String::new();
+
+    for _ in 0..count {
+        if pos + 2 > bytes.len() {
+            return Err(String::from("Dictionary file is truncated."));
+        }
+
+        let shared = bytes[pos] as usize;
+        let length = bytes[pos + 1] as usize;
+        pos += 2;
+
+        if pos + length > bytes.len() {
+            return Err(String::from("Dictionary file is truncated."));
+        }
+
+        let rest = match std::str::from_utf8(&bytes[pos..pos + length]) {
+            Ok(rest) => rest,
+            Err(_) => return Err(String::from("Dictionary file has a word that isn't UTF-8."))
+        };
+        pos += length;
+
+        let shared_bytes: usize = word.chars().take(shared).map(|c| c.len_utf8()).sum();
+        word.truncate(shared_bytes);
+        word.push_str(rest);
+
+        let frequency = match bytes.get(pos) {
+            Some(0) => None,
+            Some(1) if pos + 5 <= bytes.len() => Some(u32::from_le_bytes(bytes[pos + 1..pos + 5].try_into().unwrap())),
+            Some(1) | None => return Err(String::from("Dictionary file is truncated.")),
+            Some(_) => return Err(String::from("Dictionary file has a bad frequency marker."))
+        };
+        pos += if frequency.is_some() { 5 } else { 1 };
+
+        f(&word, frequency);
+    }
+
+    Ok(source_hash)
+}
+
+fn check_header(bytes: &[u8]) -> Result<(), String> {
+    if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+        return Err(String::from("Not a dictionary file."));
+    }
+
+    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+
+    if version != FORMAT_VERSION {
+        return Err(format!("Dictionary file is version {version}, expected {FORMAT_VERSION}."));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,235 @@
+/*
+    The `dict` subcommands, for looking after word lists. They all read
+    lists with the same rules WordTree::build_from_str uses (see
+    normalize), so what they report is what the solver will actually load.
+
+    Lists come out on stdout, sorted and one word per line, with counts on
+    stderr so the output can be redirected straight into a file. Each
+    command does its work on the text of the lists in a function of its
+    own, and only reading files and printing is left to the command.
+ */
+use std::collections::BTreeSet;
+use std::fs;
+use std::fs::read_to_string;
+use spellcast_solver::dictionary_file;
+use spellcast_solver::normalize::{NormalizePolicy, LineOutcome, LoadReport};
+use spellcast_solver::word_tree::WordTree;
+
+pub const USAGE: &str = "\
+Usage: spellcast_solver dict <command>
+    normalize <list>            cleaned up, sorted list without repeats
+    merge <list> <list>...      every word in any of the lists
+    diff <old> <new>            words removed (-) and added (+)
+    check <list>                words with letters Spellcast doesn't have
+    compile <list> <output>     binary dictionary for fast loading";
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (command, rest) = match args.split_first() {
+        Some(split) => split,
+        None => return Err(String::from(USAGE))
+    };
+
+    match (command.as_str(), rest) {
+        ("normalize", [list]) => normalize(list),
+        ("merge", lists) if !lists.is_empty() => merge(lists),
+        ("diff", [old, new]) => diff(old, new),
+        ("check", [list]) => check(list),
+        ("compile", [list, output]) => compile(list, output),
+        _ => Err(String::from(USAGE))
+    }
+}
+
+fn read_list(file_name: &str) -> Result<String, String> {
+    match read_to_string(file_name) {
+        Ok(input) => Ok(input),
+        Err(e) => Err(format!("Couldn't read {file_name}: {e}"))
+    }
+}
+
+fn print_report(file_name: &str, report: &LoadReport) {
+    eprintln!("{file_name}: {} lines, {} words, {} repeats, {} blank, {} invalid",
+        report.lines, report.accepted, report.duplicates, report.blank, report.invalid);
+}
+
+// A line the loader skips, and the characters in it that aren't letters
+#[derive(PartialEq, Debug)]
+struct InvalidLine {
+    number: usize,
+    word: String,
+    unknown: String
+}
+
+fn normalize_list(input: &str) -> Result<(Vec<String>, LoadReport), String> {
+    NormalizePolicy::default().normalize_word_list(input)
+}
+
+fn merge_lists(inputs: &[String]) -> Result<(BTreeSet<String>, Vec<LoadReport>), String> {
+    let mut merged: BTreeSet<String> = BTreeSet::new();
+    let mut reports: Vec<LoadReport> = Vec::new();
+
+    for input in inputs {
+        let (words, report) = normalize_list(input)?;
+        merged.extend(words);
+        reports.push(report);
+    }
+
+    Ok((merged, reports))
+}
+
+// Words only in old, then words only in new
+fn diff_lists(old: &str, new: &str) -> Result<(Vec<String>, Vec<String>), String> {
+    let old_words: BTreeSet<String> = normalize_list(old)?.0.into_iter().collect();
+    let new_words: BTreeSet<String> = normalize_list(new)?.0.into_iter().collect();
+
+    let removed = old_words.difference(&new_words).cloned().collect();
+    let added = new_words.difference(&old_words).cloned().collect();
+
+    Ok((removed, added))
+}
+
+/*
+    Every line the loader would skip as invalid, pointing out which
+    characters the policy doesn't read as a letter.
+ */
+fn check_list(input: &str) -> Result<(Vec<InvalidLine>, LoadReport), String> {
+    let policy = NormalizePolicy::default();
+    let mut invalid: Vec<InvalidLine> = Vec::new();
+
+    for (number, line) in (1..).zip(input.lines()) {
+        if let LineOutcome::Invalid(_) = policy.normalize_line(line) {
+            let word = policy.strip_comment(line).trim();
+            let unknown: String = word.chars()
+                .filter(|character| character.to_lowercase().any(|lower| policy.normalize_letter(lower).is_none()))
+                .collect();
+
+            invalid.push(InvalidLine { number, word: word.to_string(), unknown });
+        }
+    }
+
+    let (_, report) = policy.normalize_word_list(input)?;
+
+    Ok((invalid, report))
+}
+
+fn compile_list(input: &str) -> Result<(Vec<u8>, LoadReport), String> {
+    let (tree, report) = WordTree::try_build_from_str(input, &NormalizePolicy::default())?;
+
+    Ok((tree.to_binary(dictionary_file::hash_source(input))?, report))
+}
+
+fn normalize(list: &str) -> Result<(), String> {
+    let (words, report) = normalize_list(&read_list(list)?)?;
+
+    for word in &words {
+        println!("{word}");
+    }
+    print_report(list, &report);
+
+    Ok(())
+}
+
+fn merge(lists: &[String]) -> Result<(), String> {
+    let inputs: Vec<String> = lists.iter().map(|list| read_list(list)).collect::<Result<_, _>>()?;
+    let (merged, reports) = merge_lists(&inputs)?;
+
+    for (list, report) in lists.iter().zip(&reports) {
+        print_report(list, report);
+    }
+    for word in &merged {
+        println!("{word}");
+    }
+    eprintln!("{} words after merging", merged.len());
+
+    Ok(())
+}
+
+fn diff(old: &str, new: &str) -> Result<(), String> {
+    let (removed, added) = diff_lists(&read_list(old)?, &read_list(new)?)?;
+
+    for word in &removed {
+        println!("- {word}");
+    }
+    for word in &added {
+        println!("+ {word}");
+    }
+    eprintln!("{} removed, {} added", removed.len(), added.len());
+
+    Ok(())
+}
+
+fn check(list: &str) -> Result<(), String> {
+    let (invalid, report) = check_list(&read_list(list)?)?;
+
+    for line in &invalid {
+        println!("{list}:{}: {} ({})", line.number, line.word, line.unknown);
+    }
+    print_report(list, &report);
+
+    Ok(())
+}
+
+fn compile(list: &str, output: &str) -> Result<(), String> {
+    let (bytes, report) = compile_list(&read_list(list)?)?;
+
+    if let Err(e) = fs::write(output, bytes) {
+        return Err(format!("Couldn't write {output}: {e}"));
+    }
+    print_report(list, &report);
+    eprintln!("Wrote {output}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spellcast_solver::lexicon::Lexicon;
+
+    #[test]
+    fn test_normalize_list(){
+        let (words, report) = normalize_list("Pear\napple # fruit\n\napple\n").unwrap();
+
+        assert_eq!(words, ["apple", "pear"]);
+        assert_eq!(report, LoadReport { lines: 4, accepted: 2, duplicates: 1, blank: 1, invalid: 0 });
+    }
+
+    #[test]
+    fn test_merge_lists(){
+        let inputs = [String::from("cat\ndog"), String::from("Dog\nemu\nr2d2")];
+        let (merged, reports) = merge_lists(&inputs).unwrap();
+
+        assert_eq!(merged.into_iter().collect::<Vec<String>>(), ["cat", "dog", "emu"]);
+        assert_eq!(reports[1].invalid, 1);
+    }
+
+    #[test]
+    fn test_diff_lists(){
+        let (removed, added) = diff_lists("cat\ndog\nemu", "dog\nEMU\nfox").unwrap();
+
+        assert_eq!(removed, ["cat"]);
+        assert_eq!(added, ["fox"]);
+    }
+
+    #[test]
+    fn test_check_list(){
+        let (invalid, report) = check_list("cat\nCafé # with an accent\nr2d2\n# just a comment").unwrap();
+
+        assert_eq!(invalid, [
+            InvalidLine { number: 2, word: String::from("Café"), unknown: String::from("é") },
+            InvalidLine { number: 3, word: String::from("r2d2"), unknown: String::from("22") }
+        ]);
+        assert_eq!(report.invalid, 2);
+        assert_eq!(report.blank, 1);
+    }
+
+    #[test]
+    fn test_compile_list(){
+        let (bytes, report) = compile_list("cat\ndog\ncat").unwrap();
+        let tree = WordTree::from_binary(&bytes).unwrap();
+
+        assert_eq!(report.accepted, 2);
+        assert!(tree.is_word("cat"));
+        assert!(tree.is_word("dog"));
+        assert_eq!(dictionary_file::read_source_hash(&bytes).unwrap(), dictionary_file::hash_source("cat\ndog\ncat"));
+    }
+}
@@ -30,7 +30,7 @@ impl<T: Copy> DoubleStack<T> {
     pub fn push_new_layer(&mut self, value: T) {
         let new_layer = vec![value];
 
-        if self.stack.len() == 1 && self.stack[0].len() == 0 {
+        if self.stack.len() == 1 && self.stack[0].is_empty() {
             self.stack.pop();
         }
 
@@ -50,7 +50,7 @@ impl<T: Copy> DoubleStack<T> {
         let val = self.stack[top_index].pop().unwrap();
 
         // If that made stack frame empty, remove it
-        if self.stack[top_index].len() == 0 {
+        if self.stack[top_index].is_empty() {
             self.stack.pop();
         }
 
@@ -58,7 +58,7 @@ impl<T: Copy> DoubleStack<T> {
     }
 
     pub fn is_empty(&self) -> bool {
-        self.stack.len() <= 0
+        self.stack.is_empty()
     }
 
     #[allow(dead_code)]
@@ -71,15 +71,12 @@ impl<T: Copy + fmt::Display> fmt::Display for DoubleStack<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut stack_str = String::new();
 
-        let mut i = 0;
-        for frame in &self.stack {
+        for (i, frame) in self.stack.iter().enumerate() {
             stack_str.push_str(&format!("LAYER: {i} ["));
             for item in frame {
                 stack_str.push_str(&format!("{item}, "));
             }
             stack_str.push_str("] \n");
-
-            i = i + 1;
         }
 
         write!(f, "{stack_str}")
@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use serde::{Serialize, Deserialize};
+use crate::lexicon::Lexicon;
+use crate::letter::get_letter_slot;
+use crate::word_tree::{WordTree, LetterNode, LetterState, NodeState};
+
+// word_id of a node no word ends on
+pub const NO_WORD: u32 = u32::MAX;
+
+// parent of the root
+const NO_PARENT: u32 = u32::MAX;
+
+/*
+    The same trie as a WordTree, flattened into one Vec of nodes that refer
+    to each other by u32 id. Nodes are laid out breadth first, so all of a
+    node's children sit next to each other and only the first one needs
+    storing. They're in the same order as LetterNode's children: slotted
+    letters first in slot order (with child_mask marking them), anything
+    else after.
+
+    Every word gets an id, numbered in lexicographic order, stored on the
+    node it ends on. Per-word data like frequencies is kept in Vecs indexed
+    by word id instead of on the nodes.
+
+    Nodes are plain fixed-size structs, so the whole thing can be written
+    out and read back (or mapped) as one block.
+ */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FlatTrie {
+    nodes: Vec<FlatNode>,
+    // Node each word ends on, indexed by word id
+    word_nodes: Vec<u32>,
+    frequencies: Vec<Option<u32>>
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct FlatNode {
+    letter: char,
+    parent: u32,
+    first_child: u32,
+    child_count: u32,
+    child_mask: u32,
+    word_id: u32
+}
+
+impl FlatTrie {
+    pub fn build_from_file(file_name: &str) -> FlatTrie {
+        FlatTrie::from_tree(&WordTree::build_from_file(file_name))
+    }
+
+    pub fn build_from_str(input: &str) -> FlatTrie {
+        FlatTrie::from_tree(&WordTree::build_from_str(input))
+    }
+
+    pub fn from_tree(tree: &WordTree) -> FlatTrie {
+        let mut nodes: Vec<FlatNode> = Vec::new();
+        let mut sources: Vec<&LetterNode> = Vec::new();
+        let mut queue: VecDeque<(&LetterNode, u32)> = VecDeque::new();
+
+        queue.push_back((tree.get_root_node(), NO_PARENT));
+
+        /*
+            Breadth first, so by the time a node comes off the queue its
+            children are the next ones to be numbered.
+         */
+        while let Some((node, parent)) = queue.pop_front() {
+            let id = nodes.len() as u32;
+            let letter = match node.letter() {
+                LetterState::Present(character) => *character,
+                LetterState::Root => '\0'
+            };
+
+            nodes.push(FlatNode {
+                letter,
+                parent,
+                first_child: 0,
+                child_count: node.children().len() as u32,
+                child_mask: 0,
+                word_id: NO_WORD
+            });
+            sources.push(node);
+
+            for child in node.children() {
+                queue.push_back((child, id));
+            }
+        }
+
+        // Children were queued in order, so each node's come out in one run
+        let mut next_child = 1;
+        for node in nodes.iter_mut() {
+            node.first_child = next_child;
+            next_child += node.child_count;
+        }
+
+        for id in 1..nodes.len() {
+            let node = nodes[id];
+
+            if let Some(slot) = get_letter_slot(node.letter) {
+                nodes[node.parent as usize].child_mask |= 1 << slot;
+            }
+        }
+
+        let mut trie = FlatTrie { nodes, word_nodes: Vec::new(), frequencies: Vec::new() };
+        trie.number_words(&sources);
+
+        trie
+    }
+
+    // Depth first in child order, which is lexicographic order
+    fn number_words(&mut self, sources: &[&LetterNode]) {
+        let mut stack: Vec<u32> = vec![0];
+
+        while let Some(id) = stack.pop() {
+            let source = sources[id as usize];
+
+            if let NodeState::WordEnd = source.state() {
+                self.nodes[id as usize].word_id = self.word_nodes.len() as u32;
+                self.word_nodes.push(id);
+                self.frequencies.push(source.frequency());
+            }
+
+            let node = self.nodes[id as usize];
+            for child in (node.first_child..node.first_child + node.child_count).rev() {
+                stack.push(child);
+            }
+        }
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.word_nodes.len()
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // Bytes held on the heap by the node and word arrays
+    pub fn heap_size(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<FlatNode>()
+            + self.word_nodes.capacity() * std::mem::size_of::<u32>()
+            + self.frequencies.capacity() * std::mem::size_of::<Option<u32>>()
+    }
+
+    // Spells out the word with this id by following parents up from its node
+    pub fn word(&self, word_id: u32) -> Option<String> {
+        let mut cur = *self.word_nodes.get(word_id as usize)?;
+        let mut letters: Vec<char> = Vec::new();
+
+        while cur != 0 {
+            let node = self.nodes[cur as usize];
+            letters.push(node.letter);
+            cur = node.parent;
+        }
+
+        Some(letters.into_iter().rev().collect())
+    }
+
+    pub fn word_id_frequency(&self, word_id: u32) -> Option<u32> {
+        *self.frequencies.get(word_id as usize)?
+    }
+}
+
+impl Lexicon for FlatTrie {
+    type Node<'a> = u32;
+
+    fn root(&self) -> u32 {
+        0
+    }
+
+    fn child(&self, node: u32, character: char) -> Option<u32> {
+        let node = self.nodes[node as usize];
+        let children = node.first_child..node.first_child + node.child_count;
+
+        match get_letter_slot(character) {
+            Some(slot) => {
+                if node.child_mask & (1 << slot) == 0 {
+                    return None;
+                }
+
+                Some(node.first_child + (node.child_mask & ((1 << slot) - 1)).count_ones())
+            },
+            None => {
+                let slotted = node.child_mask.count_ones();
+
+                children.skip(slotted as usize)
+                    .find(|&child| self.nodes[child as usize].letter == character)
+            }
+        }
+    }
+
+    fn is_word_end(&self, node: u32) -> bool {
+        self.nodes[node as usize].word_id != NO_WORD
+    }
+
+    fn for_each_child<'a>(&'a self, node: u32, mut f: impl FnMut(char, Self::Node<'a>)) {
+        let node = self.nodes[node as usize];
+
+        for child in node.first_child..node.first_child + node.child_count {
+            f(self.nodes[child as usize].letter, child);
+        }
+    }
+
+    fn frequency(&self, node: u32) -> Option<u32> {
+        self.word_id_frequency(self.word_id(node)?)
+    }
+
+    fn word_id(&self, node: u32) -> Option<u32> {
+        match self.nodes[node as usize].word_id {
+            NO_WORD => None,
+            word_id => Some(word_id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    #[test]
+    fn test_words_in_flat_trie(){
+        let mut tree = WordTree::build_from_str("zebra\napple\napples\ncab\nzeal");
+        tree.add_word_to_tree("café");
+        tree.load_frequencies_from_str("cab\t40").unwrap();
+
+        let trie = FlatTrie::from_tree(&tree);
+
+        assert_eq!(trie.word_count(), 6);
+        assert_eq!(trie.node_count(), tree.node_count() + 1);
+        assert!(trie.is_word("apples"));
+        assert!(trie.is_word("café"));
+        assert!(!trie.is_word("appl"));
+        assert!(!trie.is_word("zebras"));
+
+        // Ids go in the same order as the tree's words
+        let words: Vec<String> = (0..trie.word_count() as u32).map(|id| trie.word(id).unwrap()).collect();
+        assert_eq!(words, tree.words().collect::<Vec<String>>());
+        assert_eq!(trie.find_word_id("apple"), Some(0));
+        assert_eq!(trie.find_word_id("zebra"), Some(5));
+        assert_eq!(trie.word(6), None);
+
+        assert_eq!(trie.word_frequency("cab"), Some(40));
+        assert_eq!(trie.word_frequency("zeal"), None);
+    }
+
+    #[test]
+    fn test_results_carry_word_ids(){
+        let tree = WordTree::build_from_file("./data/dictionaries/dictionary.txt");
+        let trie = FlatTrie::from_tree(&tree);
+        let mut board = Board::build_board_from_file("./data/boards/basic_board.txt").unwrap();
+        board.set_swaps(1);
+
+        let tree_results = board.get_best_words(&tree, 50);
+        let trie_results = board.get_best_words(&trie, 50);
+
+        assert_eq!(tree_results.len(), trie_results.len());
+        for (tree_result, trie_result) in tree_results.iter().zip(&trie_results) {
+            assert_eq!(tree_result.points(), trie_result.points());
+            assert_eq!(tree_result.word_id(), None);
+
+            let word_id = trie_result.word_id().unwrap();
+            assert_eq!(trie.word(word_id).unwrap(), trie_result.word());
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use std::fs;
+use serde::{Serialize, Deserialize};
+use crate::board::Board;
+use crate::letter::Modifier;
+use crate::word_result::WordResult;
+
+pub const ROUNDS: usize = 5;
+pub const STARTING_GEMS: usize = 3;
+pub const MAX_GEMS: usize = 10;
+pub const SWAP_COST: usize = 3;
+pub const SHUFFLE_COST: usize = 1;
+
+// Boards only get a double word tile from this round on
+pub const DOUBLE_WORD_FROM_ROUND: usize = 2;
+
+/*
+    A whole match: every player takes a turn each round, for ROUNDS
+    rounds. Each turn plays one word, which scores its points, picks up a
+    gem for every gem tile it goes through, and costs SWAP_COST gems per
+    swap. Gems are capped at MAX_GEMS.
+
+    Serializes to JSON so a match can be saved and picked up later.
+ */
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Game {
+    players: Vec<Player>,
+    // Starts at 1
+    round: usize,
+    // Index of the player whose turn it is
+    turn: usize,
+    history: Vec<Turn>
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Player {
+    name: String,
+    score: usize,
+    gems: usize
+}
+
+// One word someone played
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Turn {
+    round: usize,
+    player: usize,
+    word: String,
+    points: usize,
+    gems_gained: usize,
+    gems_spent: usize
+}
+
+impl Game {
+    pub fn new(player_names: &[&str]) -> Game {
+        let players = player_names.iter()
+            .map(|name| Player { name: name.to_string(), score: 0, gems: STARTING_GEMS })
+            .collect();
+
+        Game { players, round: 1, turn: 0, history: Vec::new() }
+    }
+
+    pub fn players(&self) -> &Vec<Player> {
+        &self.players
+    }
+
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn history(&self) -> &Vec<Turn> {
+        &self.history
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.round > ROUNDS || self.players.is_empty()
+    }
+
+    pub fn current_player(&self) -> Option<&Player> {
+        if self.is_over() {
+            return None;
+        }
+
+        self.players.get(self.turn)
+    }
+
+    pub fn double_word_allowed(&self) -> bool {
+        self.round >= DOUBLE_WORD_FROM_ROUND
+    }
+
+    // Whether the board could show up in this round
+    pub fn check_board(&self, board: &Board) -> Result<(), String> {
+        if !self.double_word_allowed() && count_modifier(board, &Modifier::DoubleWord) > 0 {
+            return Err(format!("There's no double word tile until round {DOUBLE_WORD_FROM_ROUND}."));
+        }
+
+        Ok(())
+    }
+
+    /*
+        Plays the word for whoever's turn it is, then moves on to the next
+        player, and the next round after the last player. The board is the
+        one the word was found on, before any swaps.
+     */
+    pub fn play_word(&mut self, board: &Board, result: &WordResult) -> Result<&Turn, String> {
+        if self.is_over() {
+            return Err(String::from("The game is over."));
+        }
+
+        self.check_board(board)?;
+
+        let gems_gained = count_gems(board, result)?;
+        let gems_spent = result.swaps().len() * SWAP_COST;
+        let player = &mut self.players[self.turn];
+
+        if gems_spent > player.gems {
+            return Err(format!("{} swaps need {gems_spent} gems, but {} only has {}.", result.swaps().len(), player.name, player.gems));
+        }
+
+        player.score += result.points();
+        player.gems = (player.gems - gems_spent + gems_gained).min(MAX_GEMS);
+
+        self.history.push(Turn {
+            round: self.round,
+            player: self.turn,
+            word: result.word().to_string(),
+            points: result.points(),
+            gems_gained,
+            gems_spent
+        });
+
+        self.turn += 1;
+        if self.turn == self.players.len() {
+            self.turn = 0;
+            self.round += 1;
+        }
+
+        Ok(&self.history[self.history.len() - 1])
+    }
+
+    // Shuffling costs gems but doesn't use up the turn
+    pub fn shuffle(&mut self) -> Result<(), String> {
+        if self.is_over() {
+            return Err(String::from("The game is over."));
+        }
+
+        let player = &mut self.players[self.turn];
+
+        if player.gems < SHUFFLE_COST {
+            return Err(format!("Shuffling needs {SHUFFLE_COST} gem, but {} has none.", player.name));
+        }
+
+        player.gems -= SHUFFLE_COST;
+
+        Ok(())
+    }
+
+    // Highest score, first player listed wins a tie
+    pub fn leader(&self) -> Option<&Player> {
+        self.players.iter().reduce(|best, player| if player.score > best.score { player } else { best })
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => Ok(json),
+            Err(e) => Err(format!("Couldn't save game: {e}"))
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Game, String> {
+        match serde_json::from_str(json) {
+            Ok(game) => Ok(game),
+            Err(e) => Err(format!("Couldn't read game: {e}"))
+        }
+    }
+
+    pub fn save(&self, file_name: &str) -> Result<(), String> {
+        match fs::write(file_name, self.to_json()?) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("Couldn't write {file_name}: {e}"))
+        }
+    }
+
+    pub fn load(file_name: &str) -> Result<Game, String> {
+        match fs::read_to_string(file_name) {
+            Ok(json) => Game::from_json(&json),
+            Err(e) => Err(format!("Couldn't read {file_name}: {e}"))
+        }
+    }
+}
+
+pub(crate) fn count_modifier(board: &Board, modifier: &Modifier) -> usize {
+    let mut count = 0;
+
+    for row in 0..board.size() {
+        for col in 0..board.size() {
+            if board.letter_at(row, col).modifiers().contains(modifier) {
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
+
+// Gem tiles the word goes through, or an error if it leaves the board
+pub(crate) fn count_gems(board: &Board, result: &WordResult) -> Result<usize, String> {
+    board.check_spaces(result)?;
+
+    Ok(board.gems_on(result))
+}
+
+impl Player {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    pub fn gems(&self) -> usize {
+        self.gems
+    }
+}
+
+impl Turn {
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+
+    pub fn points(&self) -> usize {
+        self.points
+    }
+
+    pub fn gems_gained(&self) -> usize {
+        self.gems_gained
+    }
+
+    pub fn gems_spent(&self) -> usize {
+        self.gems_spent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word_result::{word_on_cells, Swap};
+
+    #[test]
+    fn test_full_game(){
+        let board = Board::build_board_from_str("c|g a\nt s|g").unwrap();
+        let mut game = Game::new(&["ana", "ben"]);
+
+        let turn = game.play_word(&board, &word_on_cells("cats", 10, &[(1, 1), (1, 2), (2, 1), (2, 2)], Vec::new())).unwrap();
+        assert_eq!((turn.gems_gained(), turn.gems_spent()), (2, 0));
+        assert_eq!(game.current_player().unwrap().name(), "ben");
+
+        // One swap leaves ben with nothing, and then no gem to shuffle with
+        game.play_word(&board, &word_on_cells("at", 2, &[(1, 2), (2, 1)], vec![Swap::new('x', 'y', 1, 1)])).unwrap();
+        assert_eq!(game.players()[1].gems(), 0);
+        assert_eq!(game.round(), 2);
+        game.play_word(&board, &word_on_cells("at", 2, &[(1, 2), (2, 1)], Vec::new())).unwrap();
+        assert!(game.shuffle().is_err());
+        assert!(game.play_word(&board, &word_on_cells("at", 2, &[(1, 2), (2, 1)], vec![Swap::new('x', 'y', 1, 1)])).is_err());
+
+        while !game.is_over() {
+            game.play_word(&board, &word_on_cells("cats", 10, &[(1, 1), (1, 2), (2, 1), (2, 2)], Vec::new())).unwrap();
+        }
+
+        assert_eq!(game.history().len(), 2 * ROUNDS);
+        assert_eq!(game.players()[0].gems(), MAX_GEMS);
+        assert_eq!(game.leader().unwrap().name(), "ana");
+        assert!(game.current_player().is_none());
+        assert!(game.play_word(&board, &word_on_cells("at", 2, &[(1, 2), (2, 1)], Vec::new())).is_err());
+    }
+
+    #[test]
+    fn test_double_word_rounds(){
+        let board = Board::build_board_from_str("c|dw a\nt s").unwrap();
+        let mut game = Game::new(&["ana"]);
+
+        assert!(game.check_board(&board).is_err());
+        assert!(game.play_word(&board, &word_on_cells("cat", 6, &[(1, 1), (1, 2), (2, 1)], Vec::new())).is_err());
+        assert_eq!(game.round(), 1);
+
+        game.play_word(&Board::build_board_from_str("c a\nt s").unwrap(), &word_on_cells("cat", 6, &[(1, 1), (1, 2), (2, 1)], Vec::new())).unwrap();
+        assert!(game.check_board(&board).is_ok());
+    }
+
+    #[test]
+    fn test_save_and_resume(){
+        let board = Board::build_board_from_str("c|g a\nt s").unwrap();
+        let mut game = Game::new(&["ana", "ben"]);
+        game.play_word(&board, &word_on_cells("cat", 6, &[(1, 1), (1, 2), (2, 1)], Vec::new())).unwrap();
+
+        let mut resumed = Game::from_json(&game.to_json().unwrap()).unwrap();
+        assert_eq!(resumed, game);
+
+        resumed.play_word(&board, &word_on_cells("cat", 6, &[(1, 1), (1, 2), (2, 1)], Vec::new())).unwrap();
+        assert_eq!(resumed.round(), 2);
+        assert!(Game::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_spaces_off_the_board(){
+        let board = Board::build_board_from_str("c|g a\nt s").unwrap();
+        let mut game = Game::new(&["ana", "ben"]);
+
+        assert!(game.play_word(&board, &word_on_cells("cat", 6, &[(0, 1), (1, 2), (2, 1)], Vec::new())).is_err());
+        assert!(game.play_word(&board, &word_on_cells("cat", 6, &[(1, 1), (1, 3), (2, 1)], Vec::new())).is_err());
+        assert!(game.history().is_empty());
+        assert_eq!(game.current_player().unwrap().name(), "ana");
+
+        assert_eq!(count_gems(&board, &word_on_cells("cat", 6, &[(1, 1), (1, 2), (2, 1)], Vec::new())), Ok(1));
+    }
+}
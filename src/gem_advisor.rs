@@ -0,0 +1,173 @@
+use crate::board::Board;
+use crate::game::{MAX_GEMS, ROUNDS, SWAP_COST};
+use crate::lexicon::Lexicon;
+use crate::word_result::WordResult;
+
+/*
+    Rough points a gem is worth if it's kept for later. A swap tends to add
+    somewhere around 10 to 20 points to the best word, and costs SWAP_COST
+    gems.
+ */
+pub const DEFAULT_POINTS_PER_GEM: f64 = 4.0;
+
+/*
+    Helps decide how many gems to spend on swaps this turn. Solves the
+    board for every swap count the player can pay for, and weighs the
+    points each extra swap adds against what the gems would be worth if
+    kept. Kept gems are worth less the fewer rounds are left to spend them
+    in, nothing in the last round, and never count past MAX_GEMS.
+ */
+pub struct GemAdvisor {
+    points_per_gem: f64
+}
+
+pub struct SwapOption {
+    swaps: usize,
+    // Best word with up to this many swaps, if there's any word at all
+    best: Option<WordResult>,
+    // Points over the option with one fewer swap
+    marginal_points: usize,
+    // Gems after paying for the swaps and picking up any gem tiles
+    gems_left: usize,
+    value: f64
+}
+
+pub struct GemAdvice {
+    options: Vec<SwapOption>,
+    recommended: usize
+}
+
+impl GemAdvisor {
+    pub fn new(points_per_gem: f64) -> GemAdvisor {
+        GemAdvisor { points_per_gem }
+    }
+
+    /*
+        Round counts from 1, like Game. No more than max_swaps are tried,
+        however many the gems would pay for, but gems left are still worked
+        out from all the gems.
+     */
+    pub fn advise<L: Lexicon + Sync>(&self, board: &Board, lexicon: &L, gems: usize, max_swaps: usize, round: usize) -> GemAdvice {
+        let mut board = board.clone();
+        let mut options: Vec<SwapOption> = Vec::new();
+        let mut previous_points = 0;
+
+        for swaps in 0..=(gems / SWAP_COST).min(max_swaps) {
+            board.set_swaps(swaps);
+            let best = board.get_best_words(lexicon, 1).into_iter().next();
+
+            let points = best.as_ref().map_or(0, |result| result.points());
+            let gained = best.as_ref().map_or(0, |result| board.gems_on(result));
+            let gems_left = (gems - best.as_ref().map_or(0, |result| result.swaps().len()) * SWAP_COST + gained).min(MAX_GEMS);
+
+            options.push(SwapOption {
+                swaps,
+                best,
+                marginal_points: points.saturating_sub(previous_points),
+                gems_left,
+                value: points as f64 + self.kept_gem_value(gems_left, round)
+            });
+            previous_points = points;
+        }
+
+        // Fewest swaps wins a tie
+        let recommended = options.iter()
+            .enumerate()
+            .fold(0, |best, (index, option)| if option.value > options[best].value { index } else { best });
+
+        GemAdvice { options, recommended }
+    }
+
+    // Full value in the first round, down to nothing in the last
+    pub fn kept_gem_value(&self, gems: usize, round: usize) -> f64 {
+        let rounds_left = ROUNDS - round.clamp(1, ROUNDS);
+
+        gems.min(MAX_GEMS) as f64 * self.points_per_gem * rounds_left as f64 / (ROUNDS - 1) as f64
+    }
+}
+
+impl Default for GemAdvisor {
+    fn default() -> GemAdvisor {
+        GemAdvisor::new(DEFAULT_POINTS_PER_GEM)
+    }
+}
+
+impl SwapOption {
+    pub fn swaps(&self) -> usize {
+        self.swaps
+    }
+
+    pub fn best(&self) -> Option<&WordResult> {
+        self.best.as_ref()
+    }
+
+    pub fn marginal_points(&self) -> usize {
+        self.marginal_points
+    }
+
+    pub fn gems_left(&self) -> usize {
+        self.gems_left
+    }
+
+    // Points now plus what the gems left are worth later
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+impl GemAdvice {
+    pub fn options(&self) -> &Vec<SwapOption> {
+        &self.options
+    }
+
+    pub fn recommended(&self) -> &SwapOption {
+        &self.options[self.recommended]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word_tree::WordTree;
+
+    #[test]
+    fn test_gem_advice(){
+        let tree = WordTree::build_from_str("cat\ncats");
+        let board = Board::build_board_from_str("c a|g\nt x").unwrap();
+
+        let advice = GemAdvisor::new(0.5).advise(&board, &tree, 4, usize::MAX, 1);
+        assert_eq!(advice.options().len(), 2);
+
+        let (none, one) = (&advice.options()[0], &advice.options()[1]);
+        assert_eq!(none.best().unwrap().word(), "cat");
+        assert_eq!(one.best().unwrap().word(), "cats");
+        assert_eq!(none.gems_left(), 5);
+        assert_eq!(one.gems_left(), 2);
+        assert_eq!(one.marginal_points(), one.best().unwrap().points() - none.best().unwrap().points());
+        assert_eq!(advice.recommended().swaps(), 1);
+
+        // Gems are worth too much to spend, until there's no round left to spend them in
+        assert_eq!(GemAdvisor::new(100.0).advise(&board, &tree, 4, usize::MAX, 1).recommended().swaps(), 0);
+        assert_eq!(GemAdvisor::new(100.0).advise(&board, &tree, 4, usize::MAX, ROUNDS).recommended().swaps(), 1);
+
+        // Not enough for a swap
+        assert_eq!(GemAdvisor::default().advise(&board, &tree, 2, usize::MAX, 1).options().len(), 1);
+
+        // Not allowed a swap, but the gems on hand still all count
+        let capped = GemAdvisor::new(0.5).advise(&board, &tree, 9, 0, 1);
+        assert_eq!(capped.options().len(), 1);
+        assert_eq!(capped.recommended().gems_left(), 10);
+    }
+
+    #[test]
+    fn test_kept_gems_lose_value(){
+        let advisor = GemAdvisor::default();
+
+        assert_eq!(advisor.kept_gem_value(5, 1), 5.0 * DEFAULT_POINTS_PER_GEM);
+        for round in 1..ROUNDS {
+            assert!(advisor.kept_gem_value(5, round + 1) < advisor.kept_gem_value(5, round));
+        }
+        assert_eq!(advisor.kept_gem_value(5, ROUNDS), 0.0);
+        assert_eq!(advisor.kept_gem_value(MAX_GEMS + 5, 1), advisor.kept_gem_value(MAX_GEMS, 1));
+    }
+}
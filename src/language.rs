@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use crate::letter;
+use crate::normalize::{NormalizePolicy, LoadReport};
+use crate::word_tree::WordTree;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AccentFolding {
+    // Accented letters not in the alphabet make a word invalid
+    Keep,
+    // Accented letters not in the alphabet are read as the plain letter
+    Fold
+}
+
+/*
+    Everything that changes with the language a game is played in: which
+    letters there are, what they're worth, and the word list. The board
+    parser, the dictionary loader and scoring all take their alphabet from
+    one of these, and English is what they use when not given one.
+
+    Packs can be read from a file like data/languages/english.txt:
+
+        name English
+        dictionary ./data/dictionaries/dictionary.txt
+        accents keep
+        letter a 1
+        letter b 4
+        ...
+
+    with one letter line per letter of the alphabet.
+
+    The policy word lists are read with is built once from the alphabet
+    and kept up to date with the accent folding, since normalize_letter
+    gets called for every cell of every board.
+ */
+#[derive(Clone)]
+pub struct LanguagePack {
+    name: String,
+    letter_values: HashMap<char, usize>,
+    accents: AccentFolding,
+    dictionary: Option<String>,
+    policy: NormalizePolicy
+}
+
+impl LanguagePack {
+    pub fn english() -> LanguagePack {
+        LanguagePack::new(
+            String::from("English"),
+            letter::get_letter_value_map(),
+            AccentFolding::Keep,
+            Some(String::from("./data/dictionaries/dictionary.txt"))
+        )
+    }
+
+    fn new(name: String, letter_values: HashMap<char, usize>, accents: AccentFolding, dictionary: Option<String>) -> LanguagePack {
+        let mut alphabet: Vec<char> = letter_values.keys().copied().collect();
+        alphabet.sort();
+
+        let policy = NormalizePolicy::default().with_alphabet(&alphabet, accents == AccentFolding::Fold);
+
+        LanguagePack { name, letter_values, accents, dictionary, policy }
+    }
+
+    pub fn from_file(file_name: &str) -> Result<LanguagePack, String> {
+        match read_to_string(file_name) {
+            Ok(input) => LanguagePack::parse(&input),
+            Err(e) => Err(format!("Couldn't read {file_name}: {e}"))
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<LanguagePack, String> {
+        let mut name: Option<String> = None;
+        let mut letter_values: HashMap<char, usize> = HashMap::new();
+        let mut accents = AccentFolding::Keep;
+        let mut dictionary: Option<String> = None;
+
+        for (number, line) in (1..).zip(input.lines()) {
+            let line = match line.find('#') {
+                Some(index) => &line[..index],
+                None => line
+            };
+
+            let mut parts = line.split_whitespace();
+
+            match (parts.next(), parts.next(), parts.next()) {
+                (None, _, _) => (),
+                (Some("name"), Some(_), _) => name = Some(line.trim()["name".len()..].trim().to_string()),
+                (Some("dictionary"), Some(path), None) => dictionary = Some(path.to_string()),
+                (Some("accents"), Some("keep"), None) => accents = AccentFolding::Keep,
+                (Some("accents"), Some("fold"), None) => accents = AccentFolding::Fold,
+                (Some("letter"), Some(letter), Some(value)) => {
+                    let mut chars = letter.chars();
+
+                    let character = match (chars.next(), chars.next()) {
+                        (Some(character), None) => character.to_lowercase().next().unwrap_or(character),
+                        _ => return Err(format!("Line {number}: \"{letter}\" isn't a single letter"))
+                    };
+
+                    match value.parse() {
+                        Ok(value) => letter_values.insert(character, value),
+                        Err(_) => return Err(format!("Line {number}: \"{value}\" isn't a letter value"))
+                    };
+                },
+                _ => return Err(format!("Line {number}: can't read \"{}\"", line.trim()))
+            }
+        }
+
+        if letter_values.is_empty() {
+            return Err(String::from("Language pack has no letters."));
+        }
+
+        Ok(LanguagePack::new(name.unwrap_or_else(|| String::from("Unnamed")), letter_values, accents, dictionary))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn letter_values(&self) -> &HashMap<char, usize> {
+        &self.letter_values
+    }
+
+    // Sorted, so it reads like an alphabet
+    pub fn alphabet(&self) -> &[char] {
+        self.policy.alphabet.as_deref().unwrap_or_default()
+    }
+
+    pub fn accent_folding(&self) -> AccentFolding {
+        self.accents
+    }
+
+    pub fn set_accent_folding(&mut self, accents: AccentFolding) {
+        self.accents = accents;
+        self.policy.fold_accents = accents == AccentFolding::Fold;
+    }
+
+    pub fn dictionary(&self) -> Option<&str> {
+        self.dictionary.as_deref()
+    }
+
+    // How word lists get read in this language
+    pub fn normalize_policy(&self) -> &NormalizePolicy {
+        &self.policy
+    }
+
+    // The letter in this alphabet character stands for, folding if allowed
+    pub fn normalize_letter(&self, character: char) -> Option<char> {
+        let lower = character.to_lowercase().next().unwrap_or(character);
+
+        self.policy.normalize_letter(lower)
+    }
+
+    pub fn load_dictionary(&self) -> Result<(WordTree, LoadReport), String> {
+        match &self.dictionary {
+            Some(file_name) => WordTree::try_build_from_file(file_name, &self.policy),
+            None => Err(format!("The {} language pack has no dictionary.", self.name))
+        }
+    }
+
+    // What a word is worth off the board, so without any modifiers
+    pub fn word_points(&self, word: &str) -> usize {
+        letter::get_word_points_with(word, &self.letter_values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::lexicon::Lexicon;
+
+    const SPANISH: &str = "\
+# Not real Spellcast values, just enough to test with
+name Spanish
+accents fold
+letter a 1
+letter c 3
+letter i 1
+letter n 1
+letter Ñ 8
+letter o 1
+letter s 1";
+
+    #[test]
+    fn test_english_pack_file(){
+        let from_file = LanguagePack::from_file("./data/languages/english.txt").unwrap();
+        let english = LanguagePack::english();
+
+        assert_eq!(from_file.name(), english.name());
+        assert_eq!(from_file.letter_values(), english.letter_values());
+        assert_eq!(from_file.accent_folding(), english.accent_folding());
+        assert_eq!(from_file.dictionary(), english.dictionary());
+    }
+
+    #[test]
+    fn test_spanish_pack(){
+        let mut spanish = LanguagePack::parse(SPANISH).unwrap();
+
+        assert_eq!(spanish.name(), "Spanish");
+        assert_eq!(spanish.alphabet(), ['a', 'c', 'i', 'n', 'o', 's', 'ñ']);
+        assert_eq!(spanish.normalize_letter('Ñ'), Some('ñ'));
+        assert_eq!(spanish.normalize_letter('ó'), Some('o'));
+        assert_eq!(spanish.word_points("niño"), 11);
+
+        let (tree, report) = WordTree::try_build_from_str("niño\ncanción\nnonsense", spanish.normalize_policy()).unwrap();
+        assert!(tree.is_word("niño"));
+        assert!(tree.is_word("cancion"));
+        assert_eq!(report.invalid, 1);
+
+        let board = Board::build_board_from_str_in("n i\nñ o|dl", &spanish).unwrap();
+        let best = board.get_best_word(&tree);
+        assert_eq!((best.word(), best.points()), ("niño", 12));
+
+        assert_eq!(tree.anagrams_with_values("oñin", 0, spanish.letter_values()), [(String::from("niño"), 11)]);
+
+        spanish.set_accent_folding(AccentFolding::Keep);
+        assert_eq!(spanish.normalize_letter('ó'), None);
+        assert!(Board::build_board_from_str_in("n i\nó o", &spanish).is_err());
+        assert!(Board::build_board_from_str("n i\nñ o").is_err());
+
+        assert!(spanish.load_dictionary().is_err());
+        assert!(LanguagePack::parse("name Empty").is_err());
+        assert!(LanguagePack::parse("letter ab 3").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use std::fs::read_to_string;
+use crate::lexicon::Lexicon;
+use crate::normalize::{NormalizePolicy, LoadReport, LineOutcome};
+use crate::word_tree::{WordTree, LetterNode};
+
+/*
+    A base dictionary with two small overlays on top: an allow list of
+    words Spellcast accepts that the base is missing, and a deny list of
+    words it rejects. A word counts if it's in the base or the allow list,
+    and isn't in the deny list.
+
+    The overlays are WordTrees of their own, and the search walks all three
+    side by side, so patching a word never touches the base. Words going
+    into them are normalized with the same policy the base was loaded
+    with, so "Cat " and "CAT" both mean "cat".
+ */
+pub struct LayeredDictionary<L: Lexicon = WordTree> {
+    base: L,
+    allow: WordTree,
+    deny: WordTree,
+    policy: NormalizePolicy
+}
+
+/*
+    Where a prefix ends up in each layer. At least one of base and allow
+    is always Some, otherwise no word starts with the prefix.
+ */
+pub struct LayeredNode<'a, L: Lexicon + 'a> {
+    base: Option<L::Node<'a>>,
+    allow: Option<&'a LetterNode>,
+    deny: Option<&'a LetterNode>
+}
+
+impl<L: Lexicon> LayeredDictionary<L> {
+    pub fn new(base: L) -> LayeredDictionary<L> {
+        LayeredDictionary::with_policy(base, NormalizePolicy::default())
+    }
+
+    // For a base that was loaded with a policy other than the default
+    pub fn with_policy(base: L, policy: NormalizePolicy) -> LayeredDictionary<L> {
+        LayeredDictionary {
+            base,
+            allow: WordTree::new(),
+            deny: WordTree::new(),
+            policy
+        }
+    }
+
+    pub fn policy(&self) -> &NormalizePolicy {
+        &self.policy
+    }
+
+    pub fn base(&self) -> &L {
+        &self.base
+    }
+
+    pub fn allow_list(&self) -> &WordTree {
+        &self.allow
+    }
+
+    pub fn deny_list(&self) -> &WordTree {
+        &self.deny
+    }
+
+    /*
+        Adding a word to one list takes it off the other, so whatever was
+        decided last about a word wins. Both return false if the word was
+        already on that list, and an error if it isn't a valid word.
+     */
+    pub fn allow_word(&mut self, word: &str) -> Result<bool, String> {
+        let word = self.normalize(word)?;
+
+        Ok(self.allow_normalized(&word))
+    }
+
+    pub fn deny_word(&mut self, word: &str) -> Result<bool, String> {
+        let word = self.normalize(word)?;
+
+        Ok(self.deny_normalized(&word))
+    }
+
+    fn normalize(&self, word: &str) -> Result<String, String> {
+        match self.policy.normalize_line(word) {
+            LineOutcome::Word(word) => Ok(word),
+            LineOutcome::Blank => Err(format!("\"{word}\" isn't a word")),
+            LineOutcome::Invalid(reason) => Err(reason)
+        }
+    }
+
+    fn allow_normalized(&mut self, word: &str) -> bool {
+        self.deny.remove_word(word);
+        self.allow.add_word_to_tree(word)
+    }
+
+    fn deny_normalized(&mut self, word: &str) -> bool {
+        self.allow.remove_word(word);
+        self.deny.add_word_to_tree(word)
+    }
+
+    /*
+        Load an overlay from a word list, one word per line with # comments,
+        same rules as any other dictionary. Words in both files stay denied
+        whichever is loaded first.
+     */
+    pub fn load_allow_list(&mut self, file_name: &str) -> Result<LoadReport, String> {
+        let input = read_overlay(file_name)?;
+        let policy = self.policy.clone();
+
+        policy.normalize_words(&input, |word| {
+            if self.deny.contains(&word) {
+                return false;
+            }
+            self.allow.add_word_to_tree(&word)
+        })
+    }
+
+    pub fn load_deny_list(&mut self, file_name: &str) -> Result<LoadReport, String> {
+        let input = read_overlay(file_name)?;
+        let policy = self.policy.clone();
+
+        policy.normalize_words(&input, |word| self.deny_normalized(&word))
+    }
+}
+
+fn read_overlay(file_name: &str) -> Result<String, String> {
+    match read_to_string(file_name) {
+        Ok(input) => Ok(input),
+        Err(e) => Err(format!("Couldn't read {file_name}: {e}"))
+    }
+}
+
+impl<'a, L: Lexicon + 'a> Clone for LayeredNode<'a, L> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, L: Lexicon + 'a> Copy for LayeredNode<'a, L> {}
+
+impl<L: Lexicon> Lexicon for LayeredDictionary<L> {
+    type Node<'a> = LayeredNode<'a, L> where Self: 'a;
+
+    fn root(&self) -> LayeredNode<'_, L> {
+        LayeredNode {
+            base: Some(self.base.root()),
+            allow: Some(self.allow.root()),
+            deny: Some(self.deny.root())
+        }
+    }
+
+    fn child<'a>(&'a self, node: LayeredNode<'a, L>, character: char) -> Option<LayeredNode<'a, L>> {
+        let base = node.base.and_then(|base| self.base.child(base, character));
+        let allow = node.allow.and_then(|allow| allow.get_child_from_letter(character));
+
+        if base.is_none() && allow.is_none() {
+            return None;
+        }
+
+        Some(LayeredNode {
+            base,
+            allow,
+            deny: node.deny.and_then(|deny| deny.get_child_from_letter(character))
+        })
+    }
+
+    fn is_word_end<'a>(&'a self, node: LayeredNode<'a, L>) -> bool {
+        let listed = node.base.is_some_and(|base| self.base.is_word_end(base))
+            || node.allow.is_some_and(|allow| self.allow.is_word_end(allow));
+        let denied = node.deny.is_some_and(|deny| self.deny.is_word_end(deny));
+
+        listed && !denied
+    }
+
+    fn frequency<'a>(&'a self, node: LayeredNode<'a, L>) -> Option<u32> {
+        node.base.and_then(|base| self.base.frequency(base))
+            .or_else(|| node.allow.and_then(|allow| self.allow.frequency(allow)))
+    }
+
+    // Ids come from the base, so allowed words don't have one
+    fn word_id<'a>(&'a self, node: LayeredNode<'a, L>) -> Option<u32> {
+        if !self.is_word_end(node) {
+            return None;
+        }
+
+        self.base.word_id(node.base?)
+    }
+
+    /*
+        Every child the base has, with the overlays following along, then
+        whatever children only the allow list has.
+     */
+    fn for_each_child<'a>(&'a self, node: LayeredNode<'a, L>, mut f: impl FnMut(char, Self::Node<'a>)) {
+        let deny_child = |character: char| node.deny.and_then(|deny| deny.get_child_from_letter(character));
+
+        if let Some(base) = node.base {
+            self.base.for_each_child(base, |character, base_child| {
+                f(character, LayeredNode {
+                    base: Some(base_child),
+                    allow: node.allow.and_then(|allow| allow.get_child_from_letter(character)),
+                    deny: deny_child(character)
+                });
+            });
+        }
+
+        if let Some(allow) = node.allow {
+            self.allow.for_each_child(allow, |character, allow_child| {
+                if node.base.and_then(|base| self.base.child(base, character)).is_some() {
+                    return;
+                }
+
+                f(character, LayeredNode {
+                    base: None,
+                    allow: Some(allow_child),
+                    deny: deny_child(character)
+                });
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    #[test]
+    fn test_layers(){
+        let mut dictionary = LayeredDictionary::new(WordTree::build_from_str("cat\ncats\ndog"));
+
+        assert!(dictionary.allow_word("catz").unwrap());
+        assert!(dictionary.deny_word("cat").unwrap());
+
+        assert!(dictionary.is_word("catz"));
+        assert!(dictionary.is_word("cats"));
+        assert!(dictionary.is_word("dog"));
+        assert!(!dictionary.is_word("cat"));
+        assert!(!dictionary.is_word("ca"));
+
+        let mut children: Vec<char> = Vec::new();
+        let cat = dictionary.child(dictionary.child(dictionary.child(dictionary.root(), 'c').unwrap(), 'a').unwrap(), 't').unwrap();
+        dictionary.for_each_child(cat, |character, _| children.push(character));
+        assert_eq!(children, ['s', 'z']);
+
+        // Changing our minds about a word
+        assert!(dictionary.allow_word("cat").unwrap());
+        assert!(dictionary.is_word("cat"));
+        assert!(dictionary.deny_word("catz").unwrap());
+        assert!(!dictionary.is_word("catz"));
+        assert_eq!(dictionary.allow_list().words().collect::<Vec<String>>(), ["cat"]);
+        assert!(dictionary.deny_list().contains("catz"));
+
+        assert!(dictionary.load_allow_list("./data/dictionaries/missing.txt").is_err());
+    }
+
+    #[test]
+    fn test_overlay_words_are_normalized(){
+        let mut dictionary = LayeredDictionary::new(WordTree::build_from_str("cat\ndog"));
+
+        assert!(dictionary.deny_word("CAT").unwrap());
+        assert!(!dictionary.is_word("cat"));
+        assert!(!dictionary.deny_word(" cat ").unwrap());
+
+        assert!(dictionary.allow_word("Catz ").unwrap());
+        assert!(dictionary.is_word("catz"));
+
+        assert!(dictionary.allow_word("cat5").is_err());
+        assert!(dictionary.deny_word("  ").is_err());
+        assert!(!dictionary.allow_list().contains("cat5"));
+    }
+
+    #[test]
+    fn test_board_respects_layers(){
+        let tree = WordTree::build_from_file("./data/dictionaries/dictionary.txt");
+        let board = Board::build_board_from_file("./data/boards/basic_board.txt").unwrap();
+
+        let best = board.get_best_word(&tree);
+        let mut dictionary = LayeredDictionary::new(tree);
+
+        dictionary.deny_word(best.word()).unwrap();
+        assert_ne!(board.get_best_word(&dictionary).word(), best.word());
+
+        // A made up word using the first row of the board
+        let row: String = (0..5).map(|col| board.letter_at(0, col).character).collect::<String>().to_lowercase();
+        assert!(!dictionary.base().contains(&row));
+
+        dictionary.allow_word(&row).unwrap();
+        assert!(board.get_best_words(&dictionary, usize::MAX).iter().any(|result| result.word() == row));
+    }
+}
@@ -1,22 +1,25 @@
 use std::collections::HashMap;
 
+#[derive(Clone, PartialEq)]
 pub struct Letter{
     pub character: char,
     modifiers: Vec<Modifier>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum Modifier{
     Default,
     DoubleLetter,
     TripleLetter,
-    DoubleWord
+    DoubleWord,
+    // Playing a word through this cell picks up a gem
+    Gem
 }
 
 
 
 impl Letter{
-    pub fn new(character: char, modifiers: &Vec<Modifier>) -> Letter{
+    pub fn new(character: char, modifiers: &[Modifier]) -> Letter{
         let new_vec: Vec<Modifier> = modifiers.to_vec();
         Letter { character, modifiers: new_vec}
     }
@@ -27,15 +30,13 @@ impl Letter{
 
     pub fn build_letter_from_input_word(word: &str) -> Result<Letter, String> {
         let first_char = match word.chars().nth(0) {
-            Some(this_char) => this_char.to_ascii_lowercase(),
+            Some(this_char) => this_char.to_lowercase().next().unwrap_or(this_char),
             None => {
-                return Err(format!(
-                    "Unexpected empty space in board."
-                ));
+                return Err("Unexpected empty space in board.".to_string());
             }
         };
 
-        if !(word.chars().count() > 1) {
+        if word.chars().count() <= 1 {
             return Ok(Letter { character: first_char, modifiers: Vec::new() });
         }
 
@@ -57,6 +58,10 @@ impl Letter{
                 modifiers.push(Modifier::DoubleWord);
             }
 
+            if val == "g" {
+                modifiers.push(Modifier::Gem);
+            }
+
         }
 
         Ok(Letter { character: first_char, modifiers })
@@ -92,4 +97,39 @@ pub fn get_letter_value_map() -> HashMap<char, usize> {
         ('y', 4),
         ('z', 8)
     ])
-}
\ No newline at end of file
+}
+
+// Words this long score a bonus on top, which double word doesn't double
+pub const LONG_WORD_LENGTH: usize = 6;
+pub const LONG_WORD_BONUS: usize = 10;
+
+// What a word is worth off the board, so without any modifiers
+pub fn get_word_points(word: &str) -> usize {
+    get_word_points_with(word, &get_letter_value_map())
+}
+
+// Same as get_word_points, for some other language's letter values
+pub fn get_word_points_with(word: &str, letter_values: &HashMap<char, usize>) -> usize {
+    let mut points: usize = 0;
+    let mut length: usize = 0;
+
+    for letter in word.chars() {
+        points += letter_values.get(&letter.to_lowercase().next().unwrap_or(letter)).unwrap_or(&0);
+        length += 1;
+    }
+
+    if length >= LONG_WORD_LENGTH {
+        points += LONG_WORD_BONUS;
+    }
+
+    points
+}
+
+// Position of a letter in a node's child mask, if it has one
+pub(crate) fn get_letter_slot(character: char) -> Option<u32> {
+    if character.is_ascii_lowercase() {
+        Some(character as u32 - 'a' as u32)
+    } else {
+        None
+    }
+}
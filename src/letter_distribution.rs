@@ -0,0 +1,90 @@
+use crate::random::Rng;
+
+/*
+    How often each letter turns up when Spellcast fills in a tile. Letters
+    are drawn with replacement, weighted by their count.
+ */
+#[derive(Clone, Debug)]
+pub struct LetterDistribution {
+    weights: Vec<(char, u32)>,
+    total: u32
+}
+
+impl LetterDistribution {
+    pub fn new(weights: &[(char, u32)]) -> Result<LetterDistribution, String> {
+        let total: u32 = weights.iter().map(|(_, weight)| weight).sum();
+
+        if total == 0 {
+            return Err(String::from("Letter distribution needs at least one letter with a weight."));
+        }
+
+        Ok(LetterDistribution { weights: weights.to_vec(), total })
+    }
+
+    /*
+        Spellcast doesn't publish its distribution, so this uses English
+        Scrabble tile counts, which boards look close to: plenty of vowels,
+        and only one each of j, k, q, x and z.
+     */
+    pub fn spellcast() -> LetterDistribution {
+        LetterDistribution::new(&[
+            ('a', 9), ('b', 2), ('c', 2), ('d', 4), ('e', 12), ('f', 2), ('g', 3),
+            ('h', 2), ('i', 9), ('j', 1), ('k', 1), ('l', 4), ('m', 2), ('n', 6),
+            ('o', 8), ('p', 2), ('q', 1), ('r', 6), ('s', 4), ('t', 6), ('u', 4),
+            ('v', 2), ('w', 2), ('x', 1), ('y', 2), ('z', 1)
+        ]).unwrap()
+    }
+
+    pub fn weights(&self) -> &Vec<(char, u32)> {
+        &self.weights
+    }
+
+    // Chance of drawing character
+    pub fn probability(&self, character: char) -> f64 {
+        let weight: u32 = self.weights.iter()
+            .filter(|(letter, _)| *letter == character)
+            .map(|(_, weight)| weight)
+            .sum();
+
+        weight as f64 / self.total as f64
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> char {
+        let mut pick = rng.below(self.total as u64) as u32;
+
+        for &(character, weight) in &self.weights {
+            if pick < weight {
+                return character;
+            }
+            pick -= weight;
+        }
+
+        unreachable!("Weights add up to total.")
+    }
+}
+
+impl Default for LetterDistribution {
+    fn default() -> LetterDistribution {
+        LetterDistribution::spellcast()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling(){
+        let distribution = LetterDistribution::new(&[('a', 3), ('b', 1)]).unwrap();
+        let mut rng = Rng::new(42);
+
+        let draws: Vec<char> = (0..4000).map(|_| distribution.sample(&mut rng)).collect();
+        let a_count = draws.iter().filter(|&&letter| letter == 'a').count();
+        assert!((2800..3200).contains(&a_count));
+
+        assert_eq!(distribution.probability('b'), 0.25);
+        assert_eq!(distribution.probability('z'), 0.0);
+        assert!(LetterDistribution::new(&[('a', 0)]).is_err());
+        assert!((LetterDistribution::spellcast().probability('e') - 12.0 / 98.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,70 @@
+/*
+    What the board search needs from a dictionary: start at the root, step
+    to a child by letter, list a node's children (for swaps), and tell
+    whether a node ends a word. WordTree, Dawg and FlatTrie all provide
+    this, so a Board can be solved against any of them.
+
+    Nodes are small handles that are only meaningful for the dictionary
+    they came from.
+ */
+pub trait Lexicon {
+    type Node<'a>: Copy where Self: 'a;
+
+    fn root(&self) -> Self::Node<'_>;
+
+    fn child<'a>(&'a self, node: Self::Node<'a>, character: char) -> Option<Self::Node<'a>>;
+
+    fn is_word_end<'a>(&'a self, node: Self::Node<'a>) -> bool;
+
+    fn for_each_child<'a>(&'a self, node: Self::Node<'a>, f: impl FnMut(char, Self::Node<'a>));
+
+    // How common the word ending at node is, for dictionaries that know
+    fn frequency<'a>(&'a self, _node: Self::Node<'a>) -> Option<u32> {
+        None
+    }
+
+    // Id of the word ending at node, for dictionaries that number their words
+    fn word_id<'a>(&'a self, _node: Self::Node<'a>) -> Option<u32> {
+        None
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        let mut cur = self.root();
+
+        for character in word.chars() {
+            match self.child(cur, character) {
+                Some(node) => cur = node,
+                None => return false
+            }
+        }
+
+        self.is_word_end(cur)
+    }
+
+    fn word_frequency(&self, word: &str) -> Option<u32> {
+        let node = self.find_word_node(word)?;
+
+        self.frequency(node)
+    }
+
+    fn find_word_id(&self, word: &str) -> Option<u32> {
+        let node = self.find_word_node(word)?;
+
+        self.word_id(node)
+    }
+
+    // Node the word ends on, if it's a word
+    fn find_word_node(&self, word: &str) -> Option<Self::Node<'_>> {
+        let mut cur = self.root();
+
+        for character in word.chars() {
+            cur = self.child(cur, character)?;
+        }
+
+        if self.is_word_end(cur) {
+            Some(cur)
+        } else {
+            None
+        }
+    }
+}
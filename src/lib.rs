@@ -1,5 +1,22 @@
 pub mod board;
+pub mod game;
 pub mod letter;
+pub mod language;
+pub mod letter_distribution;
+pub mod random;
+pub mod planner;
+pub mod gem_advisor;
+pub mod shuffle;
+pub mod simulation;
+pub mod board_generator;
+pub mod replay;
 pub mod word_tree;
+pub mod lexicon;
+pub mod dawg;
+pub mod flat_trie;
+pub mod layered_dictionary;
+pub mod common_words;
+pub mod dictionary_file;
+pub mod normalize;
 mod double_stack;
 pub mod word_result;
\ No newline at end of file
@@ -1,11 +1,299 @@
 use spellcast_solver::word_tree::WordTree;
+use spellcast_solver::layered_dictionary::LayeredDictionary;
+use spellcast_solver::common_words::CommonWords;
+use spellcast_solver::lexicon::Lexicon;
 use spellcast_solver::board::Board;
+use spellcast_solver::language::LanguagePack;
+use spellcast_solver::gem_advisor::GemAdvisor;
+use spellcast_solver::shuffle::ShuffleEvaluator;
+use spellcast_solver::planner::Planner;
+use spellcast_solver::board_generator::BoardGenerator;
+use spellcast_solver::random::Rng;
+use spellcast_solver::replay::ReplayLog;
+use spellcast_solver::simulation::{Simulation, Strategy, Greedy, GemSaver, Lookahead};
+use std::env;
 use std::io;
+use std::path::Path;
+use std::process;
 
+mod dictionary_tools;
+
+const FREQUENCIES: &str = "./data/dictionaries/frequencies.txt";
+
+// Shuffled boards solved to decide whether to reshuffle
+const SHUFFLE_SAMPLES: usize = 50;
+
+// Words seen fewer times than this in the frequency list get flagged
+const UNCOMMON_BELOW: u32 = 1000;
+
+#[cfg(feature = "embedded-dictionary")]
+fn load_dictionary() -> WordTree {
+    WordTree::default_english()
+}
+
+#[cfg(not(feature = "embedded-dictionary"))]
+fn load_dictionary() -> WordTree {
+    // Compiles dictionary.bin on the first run, then loads that instead
+    WordTree::load_cached("./data/dictionaries/dictionary.txt", "./data/dictionaries/dictionary.bin").unwrap()
+}
+
+fn load_layered_dictionary() -> LayeredDictionary {
+    // Team lists of words Spellcast accepts or rejects, on top of the dictionary
+    let mut base = load_dictionary();
+
+    // Optional, as word<TAB>count lines
+    if Path::new(FREQUENCIES).exists() {
+        base.load_frequencies(FREQUENCIES).unwrap();
+    }
+
+    let mut tree = LayeredDictionary::new(base);
+    tree.load_allow_list("./data/dictionaries/allow.txt").unwrap();
+    tree.load_deny_list("./data/dictionaries/deny.txt").unwrap();
+
+    tree
+}
+
+/*
+    Usage:
+        spellcast_solver [board file]       solve a board
+        spellcast_solver prefix <letters>   words starting with letters
+        spellcast_solver match <pattern>    words matching a pattern, where
+                                            ? is any letter and * any run
+        spellcast_solver dict <command>     word list tools, see dictionary_tools
+        spellcast_solver gems <board file> <gems> <round>
+                                            how many swaps are worth paying for
+        spellcast_solver simulate <games> [seed]
+                                            strategies playing each other
+        spellcast_solver generate [seed] [round]
+                                            random board in board file format
+        spellcast_solver replay <log file>  best play for every turn of a game, see replay
+        spellcast_solver shuffle <board file>
+                                            whether a shuffle is worth its gem
+        spellcast_solver language <pack file> <board file>
+                                            solve a board in another language, using
+                                            the pack's letters and dictionary
+ */
 fn main() {
-    let tree = WordTree::build_from_file("./data/dictionaries/dictionary.txt");
-    let mut board = Board::build_board_from_file("./data/boards/basic_board.txt").unwrap();
+    let args: Vec<String> = env::args().collect();
+
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("prefix") => query_dictionary(&args, |tree, prefix| tree.words_with_prefix(prefix).collect()),
+        Some("match") => query_dictionary(&args, |tree, pattern| tree.matching(pattern)),
+        Some("dict") => {
+            if let Err(e) = dictionary_tools::run(&args[2..]) {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        },
+        Some("gems") => advise_gems(&args),
+        Some("simulate") => simulate(&args),
+        Some("generate") => generate(&args),
+        Some("replay") => analyze_replay(&args),
+        Some("shuffle") => evaluate_shuffle(&args),
+        Some("language") => solve_in_language(&args),
+        board_file => solve_board(board_file.unwrap_or("./data/boards/basic_board.txt"))
+    }
+}
+
+/*
+    Runs the query on the dictionary and the allow list, leaving out
+    anything on the deny list.
+ */
+fn query_dictionary(args: &[String], query: impl Fn(&WordTree, &str) -> Vec<String>) {
+    let argument = match args.get(2) {
+        Some(argument) => argument.to_lowercase(),
+        None => {
+            eprintln!("Usage: {} {} <letters or pattern>", args[0], args[1]);
+            process::exit(1);
+        }
+    };
 
+    let tree = load_layered_dictionary();
+
+    let mut words = query(tree.base(), &argument);
+    words.append(&mut query(tree.allow_list(), &argument));
+    words.retain(|word| tree.is_word(word));
+    words.sort();
+    words.dedup();
+
+    for word in &words {
+        println!("{word}");
+    }
+    println!("{} words", words.len());
+}
+
+fn advise_gems(args: &[String]) {
+    let (board_file, gems, round) = match (args.get(2), args.get(3).map(|gems| gems.parse()), args.get(4).map(|round| round.parse())) {
+        (Some(board_file), Some(Ok(gems)), Some(Ok(round))) => (board_file, gems, round),
+        _ => {
+            eprintln!("Usage: {} gems <board file> <gems> <round>", args[0]);
+            process::exit(1);
+        }
+    };
+
+    let tree = load_layered_dictionary();
+    let board = Board::build_board_from_file(board_file).unwrap();
+    let advice = GemAdvisor::default().advise(&board, &tree, gems, usize::MAX, round);
+
+    for option in advice.options() {
+        match option.best() {
+            Some(best) => println!("{} swaps: {} for {} points (+{}), {} gems left",
+                option.swaps(), best.word(), best.points(), option.marginal_points(), option.gems_left()),
+            None => println!("{} swaps: no words", option.swaps())
+        }
+    }
+
+    println!("Recommendation: {} swaps.", advice.recommended().swaps());
+}
+
+fn simulate(args: &[String]) {
+    let (games, seed) = match (args.get(2).map(|games| games.parse()), args.get(3).map(|seed| seed.parse())) {
+        (Some(Ok(games)), None) => (games, 0),
+        (Some(Ok(games)), Some(Ok(seed))) => (games, seed),
+        _ => {
+            eprintln!("Usage: {} simulate <games> [seed]", args[0]);
+            process::exit(1);
+        }
+    };
+
+    let tree = load_layered_dictionary();
+
+    // One swap at most, or a game takes minutes
+    let greedy = Greedy::new(1);
+    let saver = GemSaver::new(1, GemAdvisor::default());
+    let lookahead = Lookahead::new(1, Planner::new(5, 4, seed));
+    let strategies: [&dyn Strategy<LayeredDictionary>; 3] = [&greedy, &saver, &lookahead];
+
+    let reports = match Simulation::new(games, seed).run(&strategies, &tree) {
+        Ok(reports) => reports,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    for report in reports {
+        println!("{:<10} mean {:>6.1}, std dev {:>5.1}, {} wins, {} ties",
+            report.name(), report.mean(), report.variance().sqrt(), report.wins(), report.ties());
+    }
+}
+
+fn generate(args: &[String]) {
+    let (seed, round) = match (args.get(2).map(|seed| seed.parse()), args.get(3).map(|round| round.parse())) {
+        (None, None) => (0, 1),
+        (Some(Ok(seed)), None) => (seed, 1),
+        (Some(Ok(seed)), Some(Ok(round))) => (seed, round),
+        _ => {
+            eprintln!("Usage: {} generate [seed] [round]", args[0]);
+            process::exit(1);
+        }
+    };
+
+    print!("{}", BoardGenerator::new().for_round(round).generate_text(&mut Rng::new(seed)));
+}
+
+/*
+    Solves SHUFFLE_SAMPLES shuffles of the board, so it's kept out of the
+    plain solve. Compared without swaps, since solving every shuffle with
+    them is too slow.
+ */
+fn evaluate_shuffle(args: &[String]) {
+    let board_file = match args.get(2) {
+        Some(board_file) => board_file,
+        None => {
+            eprintln!("Usage: {} shuffle <board file>", args[0]);
+            process::exit(1);
+        }
+    };
+
+    let tree = load_layered_dictionary();
+    let board = Board::build_board_from_file(board_file).unwrap();
+    let shuffles = ShuffleEvaluator::new(SHUFFLE_SAMPLES, 0).evaluate(&board, &tree);
+
+    println!("Best without swaps: {} points", shuffles.current_best());
+    println!("After shuffling: {:.1} points expected, {} median, {} to {} for the middle 80%, {:.0}% chance of better",
+        shuffles.expected(), shuffles.percentile(50), shuffles.percentile(10), shuffles.percentile(90), shuffles.chance_of_improving() * 100.0);
+    println!("A shuffle costs about {:.1} points in gems.", shuffles.cost());
+
+    if shuffles.should_shuffle() {
+        println!("Recommendation: Reshuffle.");
+    } else {
+        println!("Recommendation: Keep the board.");
+    }
+}
+
+fn analyze_replay(args: &[String]) {
+    let log_file = match args.get(2) {
+        Some(log_file) => log_file,
+        None => {
+            eprintln!("Usage: {} replay <log file>", args[0]);
+            process::exit(1);
+        }
+    };
+
+    let tree = load_layered_dictionary();
+    let (log, analysis) = match ReplayLog::load(log_file).and_then(|log| log.analyze(&tree).map(|analysis| (log, analysis))) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    for turn in &analysis {
+        let played = turn.played();
+        print!("Round {}, {}: {} for {} points", turn.round(), turn.player(), played.word(), played.points());
+
+        match turn.best() {
+            Some(best) if turn.points_lost() > 0 => println!(", best was {} for {} with {} swaps (-{})",
+                best.word(), best.points(), best.swaps().len(), turn.points_lost()),
+            _ => println!(", the best there was")
+        }
+    }
+
+    for player in log.players() {
+        let lost: usize = analysis.iter().filter(|turn| turn.player() == player).map(|turn| turn.points_lost()).sum();
+        println!("{player} left {lost} points on the board");
+    }
+}
+
+/*
+    The team's allow and deny lists are English only, so the pack's
+    dictionary is used as it is.
+ */
+fn solve_in_language(args: &[String]) {
+    let (pack_file, board_file) = match (args.get(2), args.get(3)) {
+        (Some(pack_file), Some(board_file)) => (pack_file, board_file),
+        _ => {
+            eprintln!("Usage: {} language <pack file> <board file>", args[0]);
+            process::exit(1);
+        }
+    };
+
+    let loaded = LanguagePack::from_file(pack_file).and_then(|language| {
+        let board = Board::build_board_from_file_in(board_file, &language)?;
+        let (tree, _) = language.load_dictionary()?;
+
+        Ok((board, LayeredDictionary::with_policy(tree, language.normalize_policy().clone())))
+    });
+
+    match loaded {
+        Ok((board, tree)) => solve(board, &tree),
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    }
+}
+
+fn solve_board(board_file: &str) {
+    let tree = load_layered_dictionary();
+    let board = Board::build_board_from_file(board_file).unwrap();
+
+    solve(board, &tree);
+}
+
+fn solve(mut board: Board, tree: &LayeredDictionary) {
     println!("Number of swaps?");
     let mut num = String::new();
 
@@ -13,20 +301,20 @@ fn main() {
     let num: usize = num.trim().parse().unwrap();
     board.set_swaps(num);
 
-    let longest = board.get_longest_word(&tree);
-    let best = board.get_best_word(&tree);
-    let best_words = board.get_best_words(&tree, 10);
+    let longest = board.get_longest_word(tree);
+    let best = board.get_best_word(tree);
+    let best_words = board.get_best_words(tree, 10);
 
     println!("Longest: {} for {} points", longest.word(), longest.points());
     println!("Best: {} for {} points", best.word(), best.points());
 
-    if best.points() < 30 {
-        println!("Recommendation: Reshuffle.");
-    }
-
     println!("\n10 Best words:");
+    let common = CommonWords::new(tree, UNCOMMON_BELOW);
     for word in best_words {
-        println!("{} for {} points", word.word(), word.points());
+        match common.is_common(word.word()) {
+            Some(false) => println!("{} for {} points (uncommon)", word.word(), word.points()),
+            _ => println!("{} for {} points", word.word(), word.points())
+        }
     }
 
 
@@ -35,6 +323,3 @@ fn main() {
     let mut dummy = String::new();
     io::stdin().read_line(&mut dummy).expect("failed to exit");
 }
-
-
-
@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+
+/*
+    How the lines of a word list become words. Every loader goes through
+    here so they all agree on what's in a dictionary:
+
+    - anything from a '#' on is a comment, if comments are allowed
+    - surrounding whitespace is trimmed (this also takes care of CRLF)
+    - lines left empty are skipped
+    - words are lowercased
+    - accented letters that aren't in the alphabet are folded to the plain
+      letter (é to e), if folding is on
+    - words with anything besides the alphabet (a-z unless given) in them
+      are invalid, and are either skipped or make the whole load fail,
+      depending on the policy
+
+    This file doesn't depend on the rest of the crate, so the build script
+    can use it as well.
+ */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InvalidWords {
+    Skip,
+    Reject
+}
+
+#[derive(Clone, Debug)]
+pub struct NormalizePolicy {
+    pub invalid_words: InvalidWords,
+    pub comments: bool,
+    // Letters a word can use, or None for a-z
+    pub alphabet: Option<Vec<char>>,
+    pub fold_accents: bool
+}
+
+#[derive(PartialEq, Debug)]
+pub enum LineOutcome {
+    Word(String),
+    // Empty, whitespace or a comment
+    Blank,
+    // The reason it isn't a valid word
+    Invalid(String)
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct LoadReport {
+    pub lines: usize,
+    pub accepted: usize,
+    pub duplicates: usize,
+    pub blank: usize,
+    pub invalid: usize
+}
+
+impl NormalizePolicy {
+    pub fn new(invalid_words: InvalidWords, comments: bool) -> NormalizePolicy {
+        NormalizePolicy { invalid_words, comments, alphabet: None, fold_accents: false }
+    }
+
+    pub fn with_alphabet(mut self, alphabet: &[char], fold_accents: bool) -> NormalizePolicy {
+        self.alphabet = Some(alphabet.to_vec());
+        self.fold_accents = fold_accents;
+        self
+    }
+
+    pub fn is_letter(&self, character: char) -> bool {
+        match &self.alphabet {
+            Some(alphabet) => alphabet.contains(&character),
+            None => character.is_ascii_lowercase()
+        }
+    }
+
+    // The letter character is read as, if it's one at all. Expects lowercase.
+    pub fn normalize_letter(&self, character: char) -> Option<char> {
+        if self.is_letter(character) {
+            return Some(character);
+        }
+
+        let folded = fold_accent(character);
+
+        if self.fold_accents && self.is_letter(folded) {
+            Some(folded)
+        } else {
+            None
+        }
+    }
+
+    // line without its comment, if comments are allowed
+    pub fn strip_comment<'a>(&self, line: &'a str) -> &'a str {
+        match line.find('#') {
+            Some(index) if self.comments => &line[..index],
+            _ => line
+        }
+    }
+
+    pub fn normalize_line(&self, line: &str) -> LineOutcome {
+        let word = self.strip_comment(line).trim().to_lowercase();
+
+        if word.is_empty() {
+            return LineOutcome::Blank;
+        }
+
+        let mut normalized = String::with_capacity(word.len());
+
+        for character in word.chars() {
+            match self.normalize_letter(character) {
+                Some(letter) => normalized.push(letter),
+                None => return LineOutcome::Invalid(format!("\"{word}\" has '{character}', which isn't a letter"))
+            }
+        }
+
+        LineOutcome::Word(normalized)
+    }
+
+    /*
+        Runs every line of input through normalize_line, passing each word
+        to add, which returns false if it already had that word.
+     */
+    pub fn normalize_words(&self, input: &str, mut add: impl FnMut(String) -> bool) -> Result<LoadReport, String> {
+        let mut report = LoadReport::default();
+
+        for (number, line) in (1..).zip(input.lines()) {
+            report.lines += 1;
+
+            match self.normalize_line(line) {
+                LineOutcome::Word(word) => {
+                    if add(word) {
+                        report.accepted += 1;
+                    } else {
+                        report.duplicates += 1;
+                    }
+                },
+                LineOutcome::Blank => report.blank += 1,
+                LineOutcome::Invalid(reason) => {
+                    if self.invalid_words == InvalidWords::Reject {
+                        return Err(format!("Line {number}: {reason}"));
+                    }
+                    report.invalid += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // The words in input, sorted and without repeats
+    pub fn normalize_word_list(&self, input: &str) -> Result<(Vec<String>, LoadReport), String> {
+        let mut seen: HashSet<String> = HashSet::new();
+
+        let report = self.normalize_words(input, |word| seen.insert(word))?;
+
+        let mut words: Vec<String> = seen.into_iter().collect();
+        words.sort();
+
+        Ok((words, report))
+    }
+}
+
+impl Default for NormalizePolicy {
+    fn default() -> NormalizePolicy {
+        NormalizePolicy::new(InvalidWords::Skip, true)
+    }
+}
+
+// The letter without its accent, or the same letter if there's nothing to take off
+pub fn fold_accent(character: char) -> char {
+    match character {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => 'a',
+        'ç' | 'ć' | 'č' => 'c',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' => 'e',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => 'i',
+        'ñ' | 'ń' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ś' | 'š' => 's',
+        'ź' | 'ż' | 'ž' => 'z',
+        'ł' => 'l',
+        _ => character
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_word_list(){
+        let (words, report) = NormalizePolicy::default().normalize_word_list("pear\nApple\n\napple # again\nr2d2\n").unwrap();
+
+        assert_eq!(words, ["apple", "pear"]);
+        assert_eq!(report, LoadReport { lines: 5, accepted: 2, duplicates: 1, blank: 1, invalid: 1 });
+    }
+
+    #[test]
+    fn test_alphabets_and_accents(){
+        let spanish: Vec<char> = "abcdefghijklmnñopqrstuvwxyz".chars().collect();
+
+        let keep = NormalizePolicy::default().with_alphabet(&spanish, false);
+        assert_eq!(keep.normalize_line("Niño"), LineOutcome::Word(String::from("niño")));
+        assert!(matches!(keep.normalize_line("canción"), LineOutcome::Invalid(_)));
+
+        // ñ is its own letter here, so it doesn't get folded to n
+        let fold = NormalizePolicy::default().with_alphabet(&spanish, true);
+        assert_eq!(fold.normalize_line("Canción"), LineOutcome::Word(String::from("cancion")));
+        assert_eq!(fold.normalize_line("niño"), LineOutcome::Word(String::from("niño")));
+        assert!(matches!(fold.normalize_line("straße"), LineOutcome::Invalid(_)));
+
+        // Folding works for the default alphabet too
+        let mut english = NormalizePolicy::default();
+        assert!(matches!(english.normalize_line("café"), LineOutcome::Invalid(_)));
+        english.fold_accents = true;
+        assert_eq!(english.normalize_line("café"), LineOutcome::Word(String::from("cafe")));
+    }
+}
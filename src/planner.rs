@@ -0,0 +1,183 @@
+use crate::board::Board;
+use crate::letter_distribution::LetterDistribution;
+use crate::lexicon::Lexicon;
+use crate::random::Rng;
+use crate::word_result::WordResult;
+
+/*
+    Looks one turn ahead. Each of the best few words on the board is
+    played, the cells it used are refilled from the letter distribution,
+    and the best word on the board that leaves is found. Doing that over a
+    number of refills gives the points that word can expect to set up for
+    next turn, and plays are ranked by their points plus that.
+
+    Swaps spent now aren't there next turn, and a double word tile that
+    gets used is gone, so both show up in the next turn's best score.
+
+    In multiplayer the opponent moves next and plays the board we leave,
+    so Ranking::Denial takes their expected best off instead of adding
+    ours. Using up a double word tile or a triple letter under a z leaves
+    them less to work with, and that can beat a slightly bigger word that
+    leaves those for them.
+
+    Every candidate is tried against the same refills (sample n always
+    uses the seed plus n), so differences between candidates come from the
+    words and not from luck.
+ */
+pub struct Planner {
+    candidates: usize,
+    samples: usize,
+    seed: u64,
+    distribution: LetterDistribution,
+    ranking: Ranking
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Ranking {
+    // Our points now plus our expected best next turn
+    Lookahead,
+    // Our points now less the opponent's expected best on the board we leave
+    Denial { opponent_swaps: usize }
+}
+
+#[derive(Clone)]
+pub struct Plan {
+    result: WordResult,
+    // Average best score on the refilled board, ours or the opponent's
+    expected_next: f64,
+    value: f64
+}
+
+impl Planner {
+    pub fn new(candidates: usize, samples: usize, seed: u64) -> Planner {
+        Planner { candidates, samples, seed, distribution: LetterDistribution::spellcast(), ranking: Ranking::Lookahead }
+    }
+
+    pub fn with_distribution(mut self, distribution: LetterDistribution) -> Planner {
+        self.distribution = distribution;
+        self
+    }
+
+    pub fn with_ranking(mut self, ranking: Ranking) -> Planner {
+        self.ranking = ranking;
+        self
+    }
+
+    // Best plan first
+    pub fn plan<L: Lexicon + Sync>(&self, board: &Board, lexicon: &L) -> Vec<Plan> {
+        let mut plans: Vec<Plan> = board.get_best_words(lexicon, self.candidates)
+            .into_iter()
+            .map(|result| {
+                let expected_next = self.expected_next(board, &result, lexicon);
+                let value = match self.ranking {
+                    Ranking::Lookahead => result.points() as f64 + expected_next,
+                    Ranking::Denial { .. } => result.points() as f64 - expected_next
+                };
+
+                Plan { result, expected_next, value }
+            })
+            .collect();
+
+        plans.sort_by(|a, b| b.value().total_cmp(&a.value()));
+
+        plans
+    }
+
+    // An error if result has a space off the board
+    pub fn expected_next_score<L: Lexicon + Sync>(&self, board: &Board, result: &WordResult, lexicon: &L) -> Result<f64, String> {
+        board.check_spaces(result)?;
+
+        Ok(self.expected_next(board, result, lexicon))
+    }
+
+    // For words the board found itself, so already known to be on it
+    fn expected_next<L: Lexicon + Sync>(&self, board: &Board, result: &WordResult, lexicon: &L) -> f64 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+
+        let swaps_left = match self.ranking {
+            Ranking::Lookahead => board.swaps().saturating_sub(result.swaps().len()),
+            Ranking::Denial { opponent_swaps } => opponent_swaps
+        };
+        let mut total = 0;
+
+        for sample in 0..self.samples {
+            let mut rng = Rng::new(self.seed.wrapping_add(sample as u64));
+            let mut next = board.refill(result, &self.distribution, &mut rng);
+            next.set_swaps(swaps_left);
+
+            total += next.best_points(lexicon);
+        }
+
+        total as f64 / self.samples as f64
+    }
+}
+
+impl Plan {
+    pub fn result(&self) -> &WordResult {
+        &self.result
+    }
+
+    pub fn expected_next(&self) -> f64 {
+        self.expected_next
+    }
+
+    // What the plan was ranked by
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word_tree::WordTree;
+
+    #[test]
+    fn test_lookahead(){
+        let tree = WordTree::build_from_file("./data/dictionaries/dictionary.txt");
+        let mut board = Board::build_board_from_file("./data/boards/small_board.txt").unwrap();
+        board.set_swaps(1);
+
+        let planner = Planner::new(5, 4, 3);
+        let plans = planner.plan(&board, &tree);
+        assert_eq!(plans.len(), 5);
+
+        for pair in plans.windows(2) {
+            assert!(pair[0].value() >= pair[1].value());
+        }
+
+        // Same seed, same refills
+        let again = planner.plan(&board, &tree);
+        for (plan, other) in plans.iter().zip(&again) {
+            assert_eq!(plan.result().word(), other.result().word());
+            assert_eq!(plan.expected_next(), other.expected_next());
+        }
+
+        // Nothing left to find once every letter is one nobody can use
+        let nothing = LetterDistribution::new(&[('q', 1)]).unwrap();
+        let tiny = Board::build_board_from_str("c a\nt q").unwrap();
+        let plans = Planner::new(1, 2, 0).with_distribution(nothing).plan(&tiny, &WordTree::build_from_str("cat\nat"));
+        assert_eq!(plans[0].result().word(), "cat");
+        assert_eq!(plans[0].expected_next(), 0.0);
+    }
+
+    #[test]
+    fn test_denial_takes_the_double_word(){
+        // "at" scores far less, but uses up the double word the opponent could swap into "zax"
+        let tree = WordTree::build_from_str("zax\nax\nat");
+        let board = Board::build_board_from_str("z a|dw q\nq t q\nz a|dl x").unwrap();
+        let never = LetterDistribution::new(&[('q', 1)]).unwrap();
+        assert_eq!(board.get_best_word(&tree).word(), "zax");
+
+        let denial = Planner::new(3, 1, 0)
+            .with_distribution(never)
+            .with_ranking(Ranking::Denial { opponent_swaps: 1 })
+            .plan(&board, &tree);
+        assert_eq!(denial[0].result().word(), "at");
+        assert_eq!((denial[0].result().points(), denial[0].expected_next()), (6, 17.0));
+        assert_eq!((denial[1].result().points(), denial[1].expected_next()), (17, 32.0));
+        assert_eq!(denial[0].value(), -11.0);
+    }
+}
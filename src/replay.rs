@@ -0,0 +1,316 @@
+use std::fs;
+use serde::{Serialize, Deserialize};
+use crate::board::Board;
+use crate::game::{Game, SWAP_COST};
+use crate::lexicon::Lexicon;
+use crate::word_result::WordResult;
+
+/*
+    A record of a game as it was played: the board each player faced and
+    the word they picked, one turn per line of JSON, in turn order.
+
+        {"player":"ana","board":"c a|g\nt s\n","played":{"word":"cats",...}}
+
+    Boards are in the board file format. Gems and rounds aren't stored,
+    since replaying the turns through a Game works them out.
+
+    A player who shuffled before playing has "shuffles" set to how many
+    times they did, and the board is the one they ended up playing on.
+    Each shuffle is charged through the Game, so the gems left for swaps
+    are right, but the boards before the shuffles aren't kept, so the
+    analysis can't say whether shuffling was worth it.
+ */
+#[derive(Clone, Default)]
+pub struct ReplayLog {
+    turns: Vec<ReplayTurn>
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReplayTurn {
+    player: String,
+    board: String,
+    // Left out of older logs, which didn't record shuffles
+    #[serde(default)]
+    shuffles: usize,
+    played: WordResult
+}
+
+// One turn of a replayed game, next to the best it could have been
+pub struct TurnAnalysis {
+    round: usize,
+    player: String,
+    gems: usize,
+    played: WordResult,
+    best: Option<WordResult>
+}
+
+impl ReplayLog {
+    pub fn new() -> ReplayLog {
+        ReplayLog { turns: Vec::new() }
+    }
+
+    pub fn turns(&self) -> &Vec<ReplayTurn> {
+        &self.turns
+    }
+
+    pub fn record(&mut self, player: &str, board: &Board, played: &WordResult) {
+        self.record_shuffled(player, board, 0, played);
+    }
+
+    // A turn where the player shuffled first, board being the one after the shuffles
+    pub fn record_shuffled(&mut self, player: &str, board: &Board, shuffles: usize, played: &WordResult) {
+        self.turns.push(ReplayTurn {
+            player: player.to_string(),
+            board: board.to_input_string(),
+            shuffles,
+            played: played.clone()
+        });
+    }
+
+    pub fn to_json_lines(&self) -> Result<String, String> {
+        let mut lines = String::new();
+
+        for turn in &self.turns {
+            match serde_json::to_string(turn) {
+                Ok(line) => lines.push_str(&line),
+                Err(e) => return Err(format!("Couldn't save replay: {e}"))
+            }
+            lines.push('\n');
+        }
+
+        Ok(lines)
+    }
+
+    pub fn from_json_lines(input: &str) -> Result<ReplayLog, String> {
+        let mut turns: Vec<ReplayTurn> = Vec::new();
+
+        for (number, line) in (1..).zip(input.lines()) {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str(line) {
+                Ok(turn) => turns.push(turn),
+                Err(e) => return Err(format!("Line {number}: {e}"))
+            }
+        }
+
+        Ok(ReplayLog { turns })
+    }
+
+    pub fn save(&self, file_name: &str) -> Result<(), String> {
+        match fs::write(file_name, self.to_json_lines()?) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("Couldn't write {file_name}: {e}"))
+        }
+    }
+
+    pub fn load(file_name: &str) -> Result<ReplayLog, String> {
+        match fs::read_to_string(file_name) {
+            Ok(input) => ReplayLog::from_json_lines(&input),
+            Err(e) => Err(format!("Couldn't read {file_name}: {e}"))
+        }
+    }
+
+    // Players in the order they first took a turn, which is seat order
+    pub fn players(&self) -> Vec<&str> {
+        let mut players: Vec<&str> = Vec::new();
+
+        for turn in &self.turns {
+            if !players.contains(&turn.player.as_str()) {
+                players.push(&turn.player);
+            }
+        }
+
+        players
+    }
+
+    /*
+        Plays the log back through a Game and solves every board with as
+        many swaps as the player had gems for at the time. Played words are
+        scored again on their board rather than taking the log's word for
+        it. Fails if the log couldn't have happened, like a turn out of
+        order, a word that isn't on its board, or swaps nobody could pay
+        for.
+     */
+    pub fn analyze<L: Lexicon + Sync>(&self, lexicon: &L) -> Result<Vec<TurnAnalysis>, String> {
+        let mut game = Game::new(&self.players());
+        let mut analysis: Vec<TurnAnalysis> = Vec::new();
+
+        for (number, turn) in (1..).zip(&self.turns) {
+            let round = match game.current_player() {
+                Some(player) if player.name() == turn.player => game.round(),
+                Some(player) => return Err(format!("Turn {number}: it's {}'s turn, not {}'s", player.name(), turn.player)),
+                None => return Err(format!("Turn {number}: the game is already over"))
+            };
+
+            for _ in 0..turn.shuffles {
+                if let Err(e) = game.shuffle() {
+                    return Err(format!("Turn {number}: {e}"));
+                }
+            }
+            let gems = game.current_player().map_or(0, |player| player.gems());
+
+            let mut board = match Board::build_board_from_str(&turn.board) {
+                Ok(board) => board,
+                Err(e) => return Err(format!("Turn {number}: {e}"))
+            };
+
+            let played = match board.score_word(&turn.played) {
+                Ok(points) => rescored(&turn.played, points),
+                Err(e) => return Err(format!("Turn {number}: {e}"))
+            };
+
+            if let Err(e) = game.play_word(&board, &played) {
+                return Err(format!("Turn {number}: {e}"));
+            }
+
+            board.set_swaps(gems / SWAP_COST);
+            let best = board.get_best_words(lexicon, 1).into_iter().next();
+
+            analysis.push(TurnAnalysis { round, player: turn.player.clone(), gems, played, best });
+        }
+
+        Ok(analysis)
+    }
+}
+
+// The same word, for the points it actually scores
+fn rescored(played: &WordResult, points: usize) -> WordResult {
+    WordResult::new(played.word().to_string(), points, played.swaps().clone(), played.spaces().clone())
+        .with_word_id(played.word_id())
+}
+
+impl ReplayTurn {
+    pub fn player(&self) -> &str {
+        &self.player
+    }
+
+    pub fn shuffles(&self) -> usize {
+        self.shuffles
+    }
+
+    pub fn board(&self) -> &str {
+        &self.board
+    }
+
+    pub fn played(&self) -> &WordResult {
+        &self.played
+    }
+}
+
+impl TurnAnalysis {
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn player(&self) -> &str {
+        &self.player
+    }
+
+    // Gems before the word was played, after any shuffles
+    pub fn gems(&self) -> usize {
+        self.gems
+    }
+
+    // With the points it scores on the board, whatever the log said
+    pub fn played(&self) -> &WordResult {
+        &self.played
+    }
+
+    pub fn best(&self) -> Option<&WordResult> {
+        self.best.as_ref()
+    }
+
+    // Never negative, even for a word the dictionary doesn't have
+    pub fn points_lost(&self) -> usize {
+        let best = self.best.as_ref().map_or(0, |best| best.points());
+
+        best.saturating_sub(self.played.points())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{STARTING_GEMS, SHUFFLE_COST};
+    use crate::word_result::word_on_cells;
+    use crate::word_tree::WordTree;
+
+    #[test]
+    fn test_replay_analysis(){
+        let tree = WordTree::build_from_str("cat\ncats\nact\nat");
+        let board = Board::build_board_from_str("c a\nt s").unwrap();
+
+        let mut log = ReplayLog::new();
+        log.record("ana", &board, &word_on_cells("at", 3, &[(1, 2), (2, 1)], Vec::new()));
+        log.record("ben", &board, &word_on_cells("cats", 10, &[(1, 1), (1, 2), (2, 1), (2, 2)], Vec::new()));
+
+        let log = ReplayLog::from_json_lines(&log.to_json_lines().unwrap()).unwrap();
+        assert_eq!(log.players(), ["ana", "ben"]);
+
+        let analysis = log.analyze(&tree).unwrap();
+        assert_eq!(analysis.len(), 2);
+        assert_eq!(analysis[0].best().unwrap().word(), "cats");
+        assert_eq!(analysis[0].points_lost(), analysis[0].best().unwrap().points() - 3);
+        assert_eq!(analysis[1].points_lost(), 0);
+        assert_eq!(analysis[1].round(), 1);
+
+        // ben can't go twice in a row
+        let mut out_of_turn = ReplayLog::new();
+        out_of_turn.record("ana", &board, &word_on_cells("at", 3, &[(1, 2), (2, 1)], Vec::new()));
+        out_of_turn.record("ben", &board, &word_on_cells("at", 3, &[(1, 2), (2, 1)], Vec::new()));
+        out_of_turn.record("ben", &board, &word_on_cells("at", 3, &[(1, 2), (2, 1)], Vec::new()));
+        assert!(out_of_turn.analyze(&tree).is_err());
+
+        assert!(ReplayLog::from_json_lines("{\"player\": \"ana\"}").is_err());
+    }
+
+    #[test]
+    fn test_played_words_are_checked(){
+        let tree = WordTree::build_from_str("cat\ncats\nact\nat");
+        let board = Board::build_board_from_str("c a\nt s").unwrap();
+
+        // Row 3 isn't on a 2x2 board
+        let off_board = ReplayLog::from_json_lines(r#"{"player":"ana","board":"c a\nt s\n","played":{"word":"at","points":3,"spaces":[{"char":"a","row":1,"col":2},{"char":"t","row":3,"col":1}],"swaps":[],"word_id":null}}"#).unwrap();
+        assert!(matches!(off_board.analyze(&tree), Err(e) if e.starts_with("Turn 1:")));
+
+        let mut misspelled = ReplayLog::new();
+        misspelled.record("ana", &board, &word_on_cells("at", 3, &[(1, 2), (2, 1)], Vec::new()));
+        misspelled.record("ben", &board, &word_on_cells("cat", 8, &[(1, 2), (1, 1), (2, 1)], Vec::new()));
+        assert!(matches!(misspelled.analyze(&tree), Err(e) if e.starts_with("Turn 2:")));
+
+        // The log says 50 points, but cats is worth what it's worth on the board
+        let mut inflated = ReplayLog::new();
+        inflated.record("ana", &board, &word_on_cells("cats", 50, &[(1, 1), (1, 2), (2, 1), (2, 2)], Vec::new()));
+        let analysis = inflated.analyze(&tree).unwrap();
+        assert_eq!(analysis[0].played().points(), analysis[0].best().unwrap().points());
+        assert_eq!(analysis[0].points_lost(), 0);
+    }
+
+    #[test]
+    fn test_shuffles_cost_gems(){
+        let tree = WordTree::build_from_str("cat\ncats\nact\nat");
+        let board = Board::build_board_from_str("c a\nt s").unwrap();
+
+        let mut log = ReplayLog::new();
+        log.record_shuffled("ana", &board, 1, &word_on_cells("at", 3, &[(1, 2), (2, 1)], Vec::new()));
+        log.record("ben", &board, &word_on_cells("at", 3, &[(1, 2), (2, 1)], Vec::new()));
+
+        let log = ReplayLog::from_json_lines(&log.to_json_lines().unwrap()).unwrap();
+        assert_eq!(log.turns()[0].shuffles(), 1);
+
+        let analysis = log.analyze(&tree).unwrap();
+        assert_eq!(analysis[0].gems(), STARTING_GEMS - SHUFFLE_COST);
+        assert_eq!(analysis[1].gems(), STARTING_GEMS);
+
+        // More shuffles than there are gems to pay for
+        let mut broke = ReplayLog::new();
+        broke.record_shuffled("ana", &board, STARTING_GEMS + 1, &word_on_cells("at", 3, &[(1, 2), (2, 1)], Vec::new()));
+        assert!(matches!(broke.analyze(&tree), Err(e) if e.starts_with("Turn 1:")));
+
+        // Logs from before shuffles were recorded still load
+        let old = ReplayLog::from_json_lines(r#"{"player":"ana","board":"c a\nt s\n","played":{"word":"at","points":3,"spaces":[{"char":"a","row":1,"col":2},{"char":"t","row":2,"col":1}],"swaps":[],"word_id":null}}"#).unwrap();
+        assert_eq!(old.turns()[0].shuffles(), 0);
+    }
+}
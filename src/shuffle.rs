@@ -0,0 +1,133 @@
+use crate::board::Board;
+use crate::game::SHUFFLE_COST;
+use crate::gem_advisor::DEFAULT_POINTS_PER_GEM;
+use crate::lexicon::Lexicon;
+use crate::random::Rng;
+
+/*
+    Works out whether a shuffle is likely to help. A shuffle moves the
+    letters around but leaves the modifiers where they are, so the board
+    is shuffled a number of times, each one solved, and the best scores
+    compared against the best word on the board as it is.
+
+    Shuffling costs SHUFFLE_COST gems, so a shuffle has to be expected to
+    win back what those gems are worth (see GemAdvisor) on top.
+ */
+pub struct ShuffleEvaluator {
+    samples: usize,
+    seed: u64,
+    points_per_gem: f64
+}
+
+pub struct ShuffleReport {
+    current_best: usize,
+    // Best score on each shuffled board, lowest first
+    shuffled_best: Vec<usize>,
+    // The gems a shuffle costs, in points
+    cost: f64
+}
+
+impl ShuffleEvaluator {
+    pub fn new(samples: usize, seed: u64) -> ShuffleEvaluator {
+        ShuffleEvaluator { samples, seed, points_per_gem: DEFAULT_POINTS_PER_GEM }
+    }
+
+    pub fn with_points_per_gem(mut self, points_per_gem: f64) -> ShuffleEvaluator {
+        self.points_per_gem = points_per_gem;
+        self
+    }
+
+    pub fn evaluate<L: Lexicon + Sync>(&self, board: &Board, lexicon: &L) -> ShuffleReport {
+        let mut rng = Rng::new(self.seed);
+        let mut shuffled_best: Vec<usize> = (0..self.samples)
+            .map(|_| board.shuffled(&mut rng).best_points(lexicon))
+            .collect();
+        shuffled_best.sort();
+
+        ShuffleReport {
+            current_best: board.best_points(lexicon),
+            shuffled_best,
+            cost: SHUFFLE_COST as f64 * self.points_per_gem
+        }
+    }
+}
+
+impl ShuffleReport {
+    pub fn current_best(&self) -> usize {
+        self.current_best
+    }
+
+    pub fn shuffled_best(&self) -> &Vec<usize> {
+        &self.shuffled_best
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.cost
+    }
+
+    pub fn expected(&self) -> f64 {
+        if self.shuffled_best.is_empty() {
+            return 0.0;
+        }
+
+        self.shuffled_best.iter().sum::<usize>() as f64 / self.shuffled_best.len() as f64
+    }
+
+    // Nearest rank, so percentile(50) is the median and percentile(100) the best
+    pub fn percentile(&self, percent: usize) -> usize {
+        if self.shuffled_best.is_empty() {
+            return 0;
+        }
+
+        let rank = (percent.min(100) * self.shuffled_best.len()).div_ceil(100).max(1);
+        self.shuffled_best[rank - 1]
+    }
+
+    // Chance a shuffle turns up something better than what's there now
+    pub fn chance_of_improving(&self) -> f64 {
+        if self.shuffled_best.is_empty() {
+            return 0.0;
+        }
+
+        let better = self.shuffled_best.iter().filter(|&&points| points > self.current_best).count();
+        better as f64 / self.shuffled_best.len() as f64
+    }
+
+    // Whether a shuffle is expected to beat the board as it is, after paying for it
+    pub fn should_shuffle(&self) -> bool {
+        self.expected() - self.cost > self.current_best as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word_tree::WordTree;
+
+    #[test]
+    fn test_shuffle_report(){
+        let tree = WordTree::build_from_str("cat\nact\ntax");
+        let board = Board::build_board_from_str("x c|tl\nt a").unwrap();
+
+        let report = ShuffleEvaluator::new(20, 5).evaluate(&board, &tree);
+        assert_eq!(report.shuffled_best().len(), 20);
+        assert_eq!(report.percentile(100), *report.shuffled_best().last().unwrap());
+        assert!(report.percentile(10) <= report.percentile(50));
+        assert!(report.expected() <= report.percentile(100) as f64);
+
+        // Same seed, same shuffles
+        assert_eq!(ShuffleEvaluator::new(20, 5).evaluate(&board, &tree).shuffled_best(), report.shuffled_best());
+
+        // Nothing to find now, so any shuffle that finds a word is better
+        let stuck = Board::build_board_from_str("c x x\nx x x\nx a t").unwrap();
+        let report = ShuffleEvaluator::new(20, 5).with_points_per_gem(0.0).evaluate(&stuck, &WordTree::build_from_str("cat"));
+        assert_eq!(report.current_best(), 0);
+        assert!(report.should_shuffle());
+        assert!(report.chance_of_improving() > 0.0);
+
+        // Unless the gem it costs is worth more than a shuffle can win back
+        let report = ShuffleEvaluator::new(20, 5).with_points_per_gem(100.0).evaluate(&stuck, &WordTree::build_from_str("cat"));
+        assert_eq!(report.cost(), SHUFFLE_COST as f64 * 100.0);
+        assert!(!report.should_shuffle());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::board::Board;
+use crate::game::{Game, SWAP_COST};
+use crate::board_generator::BoardGenerator;
+use crate::gem_advisor::GemAdvisor;
+use crate::letter_distribution::LetterDistribution;
+use crate::lexicon::Lexicon;
+use crate::planner::Planner;
+use crate::random::Rng;
+use crate::word_result::WordResult;
+
+/*
+    A way of picking a word on a player's turn, given the board, their gems
+    and the round (counting from 1). None passes the turn.
+ */
+pub trait Strategy<L: Lexicon + Sync> {
+    fn name(&self) -> &str;
+
+    fn choose(&self, board: &Board, lexicon: &L, gems: usize, round: usize) -> Option<WordResult>;
+}
+
+// Best word right now, with as many swaps as the gems pay for
+pub struct Greedy {
+    max_swaps: usize
+}
+
+// As many swaps as the GemAdvisor says are worth their gems
+pub struct GemSaver {
+    max_swaps: usize,
+    advisor: GemAdvisor
+}
+
+// The Planner's first choice, with as many swaps as the gems pay for
+pub struct Lookahead {
+    max_swaps: usize,
+    planner: Planner
+}
+
+/*
+    Plays whole games between strategies to see which does best. Every
+    game gets its own seed, so a run can be repeated exactly, and the
+    strategies take turns going first.
+
+    Each round starts on a fresh board from the BoardGenerator. The letters
+    a word uses get refilled from the same letter distribution before the
+    next player's turn.
+
+    A game only counts as a win for the one player with the top score.
+    When the top score is shared, every strategy sharing it gets a tie.
+ */
+pub struct Simulation {
+    games: usize,
+    seed: u64,
+    distribution: LetterDistribution
+}
+
+pub struct StrategyReport {
+    name: String,
+    // Final score in every game, in the order the games were played
+    scores: Vec<usize>,
+    wins: usize,
+    ties: usize
+}
+
+impl Greedy {
+    pub fn new(max_swaps: usize) -> Greedy {
+        Greedy { max_swaps }
+    }
+}
+
+impl<L: Lexicon + Sync> Strategy<L> for Greedy {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    fn choose(&self, board: &Board, lexicon: &L, gems: usize, _round: usize) -> Option<WordResult> {
+        let mut board = board.clone();
+        board.set_swaps((gems / SWAP_COST).min(self.max_swaps));
+
+        board.get_best_words(lexicon, 1).into_iter().next()
+    }
+}
+
+impl GemSaver {
+    pub fn new(max_swaps: usize, advisor: GemAdvisor) -> GemSaver {
+        GemSaver { max_swaps, advisor }
+    }
+}
+
+impl<L: Lexicon + Sync> Strategy<L> for GemSaver {
+    fn name(&self) -> &str {
+        "gem saver"
+    }
+
+    fn choose(&self, board: &Board, lexicon: &L, gems: usize, round: usize) -> Option<WordResult> {
+        self.advisor.advise(board, lexicon, gems, self.max_swaps, round).recommended().best().cloned()
+    }
+}
+
+impl Lookahead {
+    pub fn new(max_swaps: usize, planner: Planner) -> Lookahead {
+        Lookahead { max_swaps, planner }
+    }
+}
+
+impl<L: Lexicon + Sync> Strategy<L> for Lookahead {
+    fn name(&self) -> &str {
+        "lookahead"
+    }
+
+    fn choose(&self, board: &Board, lexicon: &L, gems: usize, _round: usize) -> Option<WordResult> {
+        let mut board = board.clone();
+        board.set_swaps((gems / SWAP_COST).min(self.max_swaps));
+
+        self.planner.plan(&board, lexicon).into_iter().next().map(|plan| plan.result().clone())
+    }
+}
+
+impl Simulation {
+    pub fn new(games: usize, seed: u64) -> Simulation {
+        Simulation { games, seed, distribution: LetterDistribution::spellcast() }
+    }
+
+    pub fn with_distribution(mut self, distribution: LetterDistribution) -> Simulation {
+        self.distribution = distribution;
+        self
+    }
+
+    // One report per strategy, in the order they were given
+    pub fn run<L: Lexicon + Sync>(&self, strategies: &[&dyn Strategy<L>], lexicon: &L) -> Result<Vec<StrategyReport>, String> {
+        let mut reports: Vec<StrategyReport> = strategies.iter()
+            .map(|strategy| StrategyReport { name: strategy.name().to_string(), scores: Vec::new(), wins: 0, ties: 0 })
+            .collect();
+
+        for game_number in 0..self.games {
+            let mut rng = Rng::new(self.seed.wrapping_add(game_number as u64));
+
+            // Seat i is played by strategy seats[i]
+            let seats: Vec<usize> = (0..strategies.len())
+                .map(|seat| (seat + game_number) % strategies.len())
+                .collect();
+
+            let game = self.play_game(strategies, &seats, lexicon, &mut rng)?;
+
+            for (seat, player) in game.players().iter().enumerate() {
+                reports[seats[seat]].scores.push(player.score());
+            }
+
+            match top_seats(&game)[..] {
+                [seat] => reports[seats[seat]].wins += 1,
+                ref tied => {
+                    for &seat in tied {
+                        reports[seats[seat]].ties += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(reports)
+    }
+
+    fn play_game<L: Lexicon + Sync>(&self, strategies: &[&dyn Strategy<L>], seats: &[usize], lexicon: &L, rng: &mut Rng) -> Result<Game, String> {
+        // Seat numbers keep the names apart when a strategy plays itself
+        let names: Vec<String> = seats.iter()
+            .enumerate()
+            .map(|(seat, &strategy)| format!("{} {}", strategies[strategy].name(), seat + 1))
+            .collect();
+        let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+
+        let mut game = Game::new(&names);
+        let mut round = game.round();
+        let mut board = self.generator(round).generate(rng)?;
+
+        while let Some(player) = game.current_player() {
+            if game.round() != round {
+                round = game.round();
+                board = self.generator(round).generate(rng)?;
+            }
+
+            let strategy = strategies[seats[game.history().len() % seats.len()]];
+            let result = strategy.choose(&board, lexicon, player.gems(), round)
+                .unwrap_or_else(|| WordResult::new(String::new(), 0, Vec::new(), Vec::new()));
+
+            game.play_word(&board, &result)?;
+            board = board.after_play(&result, &self.distribution, rng)?;
+        }
+
+        Ok(game)
+    }
+
+    fn generator(&self, round: usize) -> BoardGenerator {
+        BoardGenerator::new()
+            .with_distribution(self.distribution.clone())
+            .for_round(round)
+    }
+}
+
+// Seats with the highest score, more than one if it's a tie
+fn top_seats(game: &Game) -> Vec<usize> {
+    let top = game.players().iter().map(|player| player.score()).max().unwrap_or(0);
+
+    game.players().iter()
+        .enumerate()
+        .filter(|(_, player)| player.score() == top)
+        .map(|(seat, _)| seat)
+        .collect()
+}
+
+impl StrategyReport {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn scores(&self) -> &Vec<usize> {
+        &self.scores
+    }
+
+    pub fn wins(&self) -> usize {
+        self.wins
+    }
+
+    pub fn ties(&self) -> usize {
+        self.ties
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.scores.is_empty() {
+            return 0.0;
+        }
+
+        self.scores.iter().sum::<usize>() as f64 / self.scores.len() as f64
+    }
+
+    // Sample variance, so it's 0 until there are two games
+    pub fn variance(&self) -> f64 {
+        if self.scores.len() < 2 {
+            return 0.0;
+        }
+
+        let mean = self.mean();
+        let squares: f64 = self.scores.iter().map(|&score| (score as f64 - mean).powi(2)).sum();
+
+        squares / (self.scores.len() - 1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word_tree::WordTree;
+
+    #[test]
+    fn test_self_play(){
+        let tree = WordTree::build_from_file("./data/dictionaries/dictionary.txt");
+        let greedy = Greedy::new(1);
+        let saver = GemSaver::new(1, GemAdvisor::default());
+        let lookahead = Lookahead::new(0, Planner::new(3, 1, 0));
+        let strategies: [&dyn Strategy<WordTree>; 3] = [&greedy, &saver, &lookahead];
+
+        let simulation = Simulation::new(3, 9);
+        let reports = simulation.run(&strategies, &tree).unwrap();
+
+        assert_eq!(reports.len(), 3);
+        // Every game is either one win or a tie between two or more
+        let wins: usize = reports.iter().map(|report| report.wins()).sum();
+        let ties: usize = reports.iter().map(|report| report.ties()).sum();
+        assert!(wins <= 3);
+        assert!(wins + ties >= 3);
+        for report in &reports {
+            assert_eq!(report.scores().len(), 3);
+            assert!(report.mean() > 0.0);
+        }
+
+        // Same seed, same games
+        let again = simulation.run(&strategies, &tree).unwrap();
+        for (report, other) in reports.iter().zip(&again) {
+            assert_eq!(report.scores(), other.scores());
+        }
+
+        let report = StrategyReport { name: String::from("test"), scores: vec![10, 20, 30], wins: 0, ties: 0 };
+        assert_eq!((report.mean(), report.variance()), (20.0, 100.0));
+    }
+
+    #[test]
+    fn test_ties_are_not_wins(){
+        let board = Board::build_board_from_str("c a\nt s").unwrap();
+        let cat = board.get_best_word(&WordTree::build_from_str("cat"));
+        let mut game = Game::new(&["a", "b", "c"]);
+
+        assert_eq!(top_seats(&game), [0, 1, 2]);
+
+        // The second and third seats tie on the same word
+        game.play_word(&board, &WordResult::new(String::new(), 0, Vec::new(), Vec::new())).unwrap();
+        game.play_word(&board, &cat).unwrap();
+        game.play_word(&board, &cat).unwrap();
+        assert_eq!(top_seats(&game), [1, 2]);
+    }
+}
@@ -6,7 +6,9 @@ pub struct WordResult {
     word: String,
     points: usize,
     spaces: Vec<Space>,
-    swaps: Vec<Swap>
+    swaps: Vec<Swap>,
+    // Set when the dictionary numbers its words, see FlatTrie
+    word_id: Option<u32>
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -30,10 +32,16 @@ impl WordResult {
             word,
             points,
             spaces,
-            swaps
+            swaps,
+            word_id: None
         }
     }
 
+    pub fn with_word_id(mut self, word_id: Option<u32>) -> WordResult {
+        self.word_id = word_id;
+        self
+    }
+
     pub fn word(&self) -> &str {
         &self.word
     }
@@ -49,6 +57,10 @@ impl WordResult {
     pub fn swaps(&self) -> &Vec<Swap> {
         &self.swaps
     }
+
+    pub fn word_id(&self) -> Option<u32> {
+        self.word_id
+    }
 }
 
 impl Swap {
@@ -106,3 +118,10 @@ impl Space {
     }
 }
 
+// A word played on the given cells, which count from 1 like Space's
+#[cfg(test)]
+pub(crate) fn word_on_cells(word: &str, points: usize, cells: &[(usize, usize)], swaps: Vec<Swap>) -> WordResult {
+    let spaces = word.chars().zip(cells).map(|(character, &(row, col))| Space::new(character, row, col)).collect();
+
+    WordResult::new(word.to_string(), points, swaps, spaces)
+}
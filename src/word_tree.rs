@@ -1,18 +1,35 @@
 use std::fmt;
-use std::fs::read_to_string;
+use std::collections::HashMap;
+use std::fs::{self, read_to_string};
 use serde::{Serialize, Deserialize};
+use crate::lexicon::Lexicon;
+use crate::letter::{self, get_letter_slot};
+use crate::dictionary_file;
+use crate::normalize::{NormalizePolicy, LoadReport, LineOutcome, InvalidWords};
+
+#[cfg(feature = "embedded-dictionary")]
+static DEFAULT_ENGLISH: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/dictionary.bin"));
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct WordTree {
     root: LetterNode,
 }
 
+/*
+    Children that have a slot (see letter::get_letter_slot) come first, in slot
+    order, with a bit set in child_mask for each one. That way finding a
+    child is a mask check and a popcount instead of a scan. Any other
+    characters are kept after them and still looked up one by one.
+ */
 #[derive(Serialize, Deserialize, Clone)]
 pub struct LetterNode{
     letter: LetterState,
     children: Vec<LetterNode>,
+    child_mask: u32,
     state: NodeState,
-    level: usize
+    level: usize,
+    // How common the word ending here is, if we know
+    frequency: Option<u32>
 
 }
 
@@ -33,119 +50,127 @@ impl WordTree {
         WordTree::build_from_str(&read_to_string(file_name).unwrap())
     }
 
-    pub fn build_from_str(input: &str) -> WordTree {
+    pub fn new() -> WordTree {
         let root = LetterNode{
             letter: LetterState::Root,
             children: Vec::new(),
+            child_mask: 0,
             state: NodeState::WordMiddle,
-            level: 0
+            level: 0,
+            frequency: None
         };
 
-        let mut tree  = WordTree{
-            root: root
-        };
+        WordTree{
+            root
+        }
+    }
+
+    /*
+        The bundled English dictionary (data/dictionaries/dictionary.txt),
+        compiled into the library so it works from any directory.
+     */
+    #[cfg(feature = "embedded-dictionary")]
+    pub fn default_english() -> WordTree {
+        WordTree::from_binary(DEFAULT_ENGLISH).expect("Embedded dictionary is invalid.")
+    }
 
-        let words = input.lines();
+    // Anything that isn't a word gets skipped, see normalize for the rules
+    pub fn build_from_str(input: &str) -> WordTree {
+        match WordTree::try_build_from_str(input, &NormalizePolicy::default()) {
+            Ok((tree, _)) => tree,
+            Err(e) => panic!("Skipping invalid words shouldn't fail: {e}")
+        }
+    }
 
-        for word in words{
-            tree.add_word_to_tree(&word.to_lowercase());
+    pub fn try_build_from_file(file_name: &str, policy: &NormalizePolicy) -> Result<(WordTree, LoadReport), String> {
+        match read_to_string(file_name) {
+            Ok(input) => WordTree::try_build_from_str(&input, policy),
+            Err(e) => Err(format!("Couldn't read {file_name}: {e}"))
         }
+    }
+
+    pub fn try_build_from_str(input: &str, policy: &NormalizePolicy) -> Result<(WordTree, LoadReport), String> {
+        let mut tree = WordTree::new();
+
+        let report = policy.normalize_words(input, |word| tree.add_word_to_tree(&word))?;
 
-        tree
+        Ok((tree, report))
     }
 
     /*
         Starting at root node, traverse character by character until
-        at end of word
+        at end of word, then mark that node as the end of a word.
+        Returns false if the word was already in the tree.
+
+        Words go in exactly as given, so run them through normalize first
+        if they come from anywhere but a loaded dictionary.
      */
-    fn add_word_to_tree(&mut self, word: &str){
-        let char_list = word.chars();
+    pub fn add_word_to_tree(&mut self, word: &str) -> bool {
+        if word.is_empty() {
+            return false;
+        }
 
         let mut cur = &mut self.root;
 
-        let mut char_counter = 1;
-
-        let char_count = char_list.to_owned().count();
-    
-        let mut depth: usize = 1;
-
-        /* 
+        /*
             For every character in the source word,
             check if it's in the tree already. If not,
             add to the tree by constructing a new Node
-            and pushing it to the current Node's
+            and inserting it into the current Node's
             children.
 
             If already in, go on to next character
         */
-        for character in char_list{
-            /*
-                Is there a way I can avoid calling the is char in twice?
-                Probably not much of a performance hit but I'm curious more
-                than anything
-             */
-            match cur.get_char_in_children(character){
-                // Some: Already in
-                Some(_) => (),
-                // None: Not in tree
-                None => {
-                    if char_counter == char_count{
-                        cur.children.push(LetterNode { 
-                            letter: LetterState::Present(character), 
-                            children: Vec::new(), 
-                            state: NodeState::WordEnd,
-                            level: depth
-                        });
-                    }
-                    else{
-                        cur.children.push(LetterNode { 
-                            letter: LetterState::Present(character), 
-                            children: Vec::new(), 
-                            state: NodeState::WordMiddle,
-                            level: depth
-                        });
-                    }
-                }
+        for (depth, character) in (1..).zip(word.chars()){
+            if cur.get_child_index(character).is_none() {
+                cur.insert_child(LetterNode {
+                    letter: LetterState::Present(character),
+                    children: Vec::new(),
+                    child_mask: 0,
+                    state: NodeState::WordMiddle,
+                    level: depth,
+                    frequency: None
+                });
             }
 
-            char_counter += 1;
-            depth += 1;
-
             cur = cur.get_char_in_children(character).unwrap();
         }
+
+        // The node might already be there as part of a longer word
+        match cur.state {
+            NodeState::WordEnd => false,
+            NodeState::WordMiddle => {
+                cur.state = NodeState::WordEnd;
+                true
+            }
+        }
+    }
+
+    /*
+        Unmarks the word, then removes any nodes left that don't lead to a
+        word anymore. Returns false if the word wasn't in the tree.
+     */
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        let characters: Vec<char> = word.chars().collect();
+
+        if characters.is_empty() {
+            return false;
+        }
+
+        self.root.remove_below(&characters)
     }
 
     pub fn is_word_in_tree(&self, word: &str) -> bool {
-        // Get the children underneath the current root node
-        let mut children = &self.root.children;
+        let mut cur = &self.root;
 
-        let mut letter_found = false;
-        let mut last_node = &children[0];
-        
-        
         for letter in word.chars(){
-            for node in children {
-                match node.letter {
-                    LetterState::Present(character) => { 
-                        if character == letter {
-                            letter_found = true;
-                            children = &node.children;
-                            last_node = node;
-                            break;
-                        }
-                    },
-                    LetterState::Root => ()
-                }
-            }
-
-            if !letter_found {
-                return false;
+            match cur.get_child_from_letter(letter) {
+                Some(node) => cur = node,
+                None => return false
             }
-            letter_found = false;
-
         }
 
-        match last_node.state {
+        match cur.state {
             NodeState::WordEnd => true,
             NodeState::WordMiddle => false
         }
@@ -156,30 +181,595 @@ impl WordTree {
         &self.root
     }
 
+    // Returns false if word isn't in the tree
+    pub fn set_frequency(&mut self, word: &str, frequency: u32) -> bool {
+        let mut cur = &mut self.root;
+
+        for character in word.chars() {
+            match cur.get_char_in_children(character) {
+                Some(node) => cur = node,
+                None => return false
+            }
+        }
+
+        match cur.state {
+            NodeState::WordEnd => {
+                cur.frequency = Some(frequency);
+                true
+            },
+            NodeState::WordMiddle => false
+        }
+    }
+
+    pub fn load_frequencies(&mut self, file_name: &str) -> Result<usize, String> {
+        match read_to_string(file_name) {
+            Ok(input) => self.load_frequencies_from_str(&input),
+            Err(e) => Err(format!("Couldn't read {file_name}: {e}"))
+        }
+    }
+
+    /*
+        Reads "word<TAB>count" lines, like a word list with a count after
+        each word. Counts for words that aren't in the tree are ignored.
+        Returns how many words in the tree got a frequency.
+     */
+    pub fn load_frequencies_from_str(&mut self, input: &str) -> Result<usize, String> {
+        self.load_frequencies_with(input, &NormalizePolicy::default())
+    }
+
+    /*
+        Same, with the words read by policy, which should be the one the
+        tree was loaded with so the words line up. Comments and invalid
+        words are handled the way the policy says.
+     */
+    pub fn load_frequencies_with(&mut self, input: &str, policy: &NormalizePolicy) -> Result<usize, String> {
+        let mut found = 0;
+
+        for (number, line) in (1..).zip(input.lines()) {
+            let line = policy.strip_comment(line);
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (word, count) = match line.split_once('\t') {
+                Some(split) => split,
+                None => return Err(format!("Line {number}: expected a word and a count separated by a tab"))
+            };
+
+            let count: u64 = match count.trim().parse() {
+                Ok(count) => count,
+                Err(_) => return Err(format!("Line {number}: \"{}\" isn't a count", count.trim()))
+            };
+
+            let word = match policy.normalize_line(word) {
+                LineOutcome::Word(word) => word,
+                LineOutcome::Blank => return Err(format!("Line {number}: there's no word before the count")),
+                LineOutcome::Invalid(reason) => {
+                    if policy.invalid_words == InvalidWords::Reject {
+                        return Err(format!("Line {number}: {reason}"));
+                    }
+                    continue;
+                }
+            };
+
+            if self.set_frequency(&word, count.min(u32::MAX as u64) as u32) {
+                found += 1;
+            }
+        }
+
+        Ok(found)
+    }
+
+    /*
+        Saving and loading in the binary format (see dictionary_file).
+        source_hash should be dictionary_file::hash_source of the text the
+        tree was built from, so a stale file can be spotted later.
+        Frequencies are saved along with the words.
+     */
+    pub fn to_binary(&self, source_hash: u64) -> Result<Vec<u8>, String> {
+        let words: Vec<(String, Option<u32>)> = self.words()
+            .map(|word| {
+                let frequency = self.word_frequency(&word);
+                (word, frequency)
+            })
+            .collect();
+
+        dictionary_file::encode(words.iter().map(|(word, frequency)| (word.as_str(), *frequency)), source_hash)
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> Result<WordTree, String> {
+        let mut tree = WordTree::new();
+
+        dictionary_file::decode_each(bytes, |word, frequency| {
+            tree.add_word_to_tree(word);
+
+            if let Some(frequency) = frequency {
+                tree.set_frequency(word, frequency);
+            }
+        })?;
+
+        Ok(tree)
+    }
+
+    pub fn save_binary(&self, file_name: &str, source_hash: u64) -> Result<(), String> {
+        match fs::write(file_name, self.to_binary(source_hash)?) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("Couldn't write {file_name}: {e}"))
+        }
+    }
+
+    pub fn load_binary(file_name: &str) -> Result<WordTree, String> {
+        match fs::read(file_name) {
+            Ok(bytes) => WordTree::from_binary(&bytes),
+            Err(e) => Err(format!("Couldn't read {file_name}: {e}"))
+        }
+    }
+
+    /*
+        Load a word list through its compiled binary file if that was built
+        from the same text. Otherwise build from the text and write the binary
+        file for next time. Failing to write it isn't an error, it just means
+        the next run builds from text again.
+     */
+    pub fn load_cached(source_file: &str, binary_file: &str) -> Result<WordTree, String> {
+        let source = match read_to_string(source_file) {
+            Ok(source) => source,
+            Err(e) => return Err(format!("Couldn't read {source_file}: {e}"))
+        };
+        let source_hash = dictionary_file::hash_source(&source);
+
+        if let Ok(bytes) = fs::read(binary_file) {
+            if dictionary_file::read_source_hash(&bytes) == Ok(source_hash) {
+                return WordTree::from_binary(&bytes);
+            }
+        }
+
+        let tree = WordTree::build_from_str(&source);
+        let _ = tree.save_binary(binary_file, source_hash);
+
+        Ok(tree)
+    }
+
+    /*
+        Every word in the tree in lexicographic order, or at least it is for
+        a-z words. Anything else sorts after z, since that's how children are
+        stored.
+     */
+    pub fn words(&self) -> Words<'_> {
+        Words {
+            stack: vec![self.root.children.iter()],
+            word: String::new()
+        }
+    }
+
+    // Same order as words, just the ones starting with prefix
+    pub fn words_with_prefix(&self, prefix: &str) -> Words<'_> {
+        let mut cur = &self.root;
+
+        for character in prefix.chars() {
+            match cur.get_child_from_letter(character) {
+                Some(node) => cur = node,
+                None => return Words { stack: Vec::new(), word: String::new() }
+            }
+        }
+
+        if prefix.is_empty() {
+            return self.words();
+        }
+
+        /*
+            Start as if about to step into the prefix's last node, so the
+            prefix itself comes out first if it's a word.
+         */
+        let mut word = prefix.to_string();
+        word.pop();
+
+        Words {
+            stack: vec![std::slice::from_ref(cur).iter()],
+            word
+        }
+    }
+
+    /*
+        Words matching a glob pattern, sorted. '?' is any one letter, '*' is
+        any number of letters (including none), anything else has to match
+        exactly. So "?x?de" is five letters with x second and "de" on the
+        end, and "?????*ing" is words of 8 or more letters ending in "ing".
+     */
+    pub fn matching(&self, pattern: &str) -> Vec<String> {
+        let mut glob: Vec<char> = Vec::new();
+
+        // Runs of '*' match the same as one, and are a lot slower
+        for character in pattern.chars() {
+            if !(character == '*' && glob.last() == Some(&'*')) {
+                glob.push(character);
+            }
+        }
+
+        let mut words: Vec<String> = Vec::new();
+        let mut word = String::new();
+
+        add_matches_below(&self.root, &glob, &mut word, &mut words);
+
+        // A '*' can match the same word more than one way
+        words.sort();
+        words.dedup();
+
+        words
+    }
+
+    /*
+        Every word that can be spelled from letters, using each one at most
+        as many times as it's given, with blanks standing in for any letter.
+        A blank scores as the letter it stands for, the same as a swap does.
+
+        Returns words with their points (no board modifiers), best first.
+     */
+    pub fn anagrams(&self, letters: &str, blanks: usize) -> Vec<(String, usize)> {
+        self.anagrams_with_values(letters, blanks, &letter::get_letter_value_map())
+    }
+
+    // Same as anagrams, scored with some other language's letter values
+    pub fn anagrams_with_values(&self, letters: &str, blanks: usize, letter_values: &HashMap<char, usize>) -> Vec<(String, usize)> {
+        let mut counts = LetterCounts::new(letters);
+
+        let mut words: Vec<String> = Vec::new();
+        let mut word = String::new();
+
+        add_anagrams_below(&self.root, &mut counts, blanks, &mut word, &mut words);
+
+        let mut scored: Vec<(String, usize)> = words.into_iter()
+            .map(|word| {
+                let points = letter::get_word_points_with(&word, letter_values);
+                (word, points)
+            })
+            .collect();
+
+        scored.sort_by(|(a_word, a_points), (b_word, b_points)| b_points.cmp(a_points).then_with(|| a_word.cmp(b_word)));
+
+        scored
+    }
+
+    /*
+        A copy of the tree with only the words anagrams would find for the
+        same letters and blanks, so a search that can only ever spell those
+        doesn't have to wade through the rest.
+     */
+    pub fn pruned_for_letters(&self, letters: &str, blanks: usize) -> WordTree {
+        let mut counts = LetterCounts::new(letters);
+
+        WordTree {
+            root: prune_below(&self.root, &mut counts, blanks)
+        }
+    }
+
+    // Walks the whole tree, so keep hold of it rather than calling in a loop
+    pub fn len(&self) -> usize {
+        self.stats().words
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.children.is_empty()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.is_word_in_tree(word)
+    }
+
+    // Index is word length in letters, value is how many words have it
+    pub fn length_histogram(&self) -> Vec<usize> {
+        self.stats().by_length
+    }
+
+    // Not counting the root
+    pub fn node_count(&self) -> usize {
+        self.stats().nodes
+    }
+
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+
+        add_stats_below(&self.root, &mut stats);
+
+        stats
+    }
+
+}
+
+/*
+    Depth first walk over the tree, keeping the path to the current node.
+    A word comes out when its last letter is reached, before any longer
+    words that start with it.
+ */
+pub struct Words<'a> {
+    stack: Vec<std::slice::Iter<'a, LetterNode>>,
+    word: String
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct TreeStats {
+    pub words: usize,
+    pub nodes: usize,
+    // Nodes without children, so the end of a word nothing else extends
+    pub leaves: usize,
+    // Same as length_histogram
+    pub by_length: Vec<usize>
 }
 
+impl<'a> Iterator for Words<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while let Some(children) = self.stack.last_mut() {
+            match children.next() {
+                Some(child) => {
+                    if let LetterState::Present(character) = child.letter {
+                        self.word.push(character);
+                    }
+                    self.stack.push(child.children.iter());
+
+                    if let NodeState::WordEnd = child.state {
+                        return Some(self.word.clone());
+                    }
+                },
+                None => {
+                    self.stack.pop();
+                    self.word.pop();
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/*
+    A summary of what's in the tree: word and node counts, then how many
+    words there are of each length. {:#} lists every word after that too.
+ */
 impl fmt::Display for WordTree {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
-        let root = &self.root;
+        let stats = self.stats();
 
-        let mut i = 1;
+        writeln!(f, "{} words, {} nodes ({} leaves)", stats.words, stats.nodes, stats.leaves)?;
 
-        let mut tree_str = String::new();
+        for (length, count) in stats.by_length.iter().enumerate() {
+            if *count > 0 {
+                writeln!(f, "{length:>3} letters: {count}")?;
+            }
+        }
 
-        // FIX THIS IT NEEDS TO PRINT CORRECTLY
-        while root.children.len() > 0{
-            for letter in &root.children{
-                match letter.letter{
-                    LetterState::Present(character) => { tree_str.push_str(&format!(" ({i}) {character}")) },
-                    LetterState::Root => ()
+        if f.alternate() {
+            for word in self.words() {
+                writeln!(f, "{word}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for WordTree {
+    fn default() -> WordTree {
+        WordTree::new()
+    }
+}
+
+fn add_matches_below(node: &LetterNode, glob: &[char], word: &mut String, words: &mut Vec<String>) {
+    let (first, rest) = match glob.split_first() {
+        Some(split) => split,
+        None => {
+            if let NodeState::WordEnd = node.state {
+                words.push(word.clone());
+            }
+            return;
+        }
+    };
+
+    match first {
+        '*' => {
+            // Matching nothing, then matching one more letter and staying on the '*'
+            add_matches_below(node, rest, word, words);
+
+            for child in &node.children {
+                if let LetterState::Present(character) = child.letter {
+                    word.push(character);
+                    add_matches_below(child, glob, word, words);
+                    word.pop();
                 }
             }
-            tree_str.push('\n');
-            i = i + 1;
+        },
+        '?' => {
+            for child in &node.children {
+                if let LetterState::Present(character) = child.letter {
+                    word.push(character);
+                    add_matches_below(child, rest, word, words);
+                    word.pop();
+                }
+            }
+        },
+        &character => {
+            if let Some(child) = node.get_child_from_letter(character) {
+                word.push(character);
+                add_matches_below(child, rest, word, words);
+                word.pop();
+            }
+        }
+    }
+}
+
+/*
+    How many of each letter are left to spell with. a-z get a slot each,
+    so the usual alphabet is cheap, and any other letters go in a list.
+ */
+struct LetterCounts {
+    slotted: [usize; 26],
+    other: Vec<(char, usize)>
+}
+
+impl LetterCounts {
+    fn new(letters: &str) -> LetterCounts {
+        let mut counts = LetterCounts { slotted: [0; 26], other: Vec::new() };
+
+        for character in letters.to_lowercase().chars() {
+            counts.put_back(character);
+        }
+
+        counts
+    }
+
+    fn count_mut(&mut self, character: char) -> Option<&mut usize> {
+        match get_letter_slot(character) {
+            Some(slot) => Some(&mut self.slotted[slot as usize]),
+            None => self.other.iter_mut()
+                .find(|(letter, _)| *letter == character)
+                .map(|(_, count)| count)
+        }
+    }
+
+    // Uses up one of the letter, if there's one left
+    fn take(&mut self, character: char) -> bool {
+        match self.count_mut(character) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            },
+            _ => false
+        }
+    }
+
+    fn put_back(&mut self, character: char) {
+        match self.count_mut(character) {
+            Some(count) => *count += 1,
+            None => self.other.push((character, 1))
+        }
+    }
+}
+
+/*
+    Copy of node keeping only the children that still lead to a word with
+    the letters left. Uses up letters and blanks the same way
+    add_anagrams_below does.
+ */
+fn prune_below(node: &LetterNode, counts: &mut LetterCounts, blanks: usize) -> LetterNode {
+    let mut pruned = LetterNode {
+        letter: node.letter.clone(),
+        children: Vec::new(),
+        child_mask: 0,
+        state: node.state.clone(),
+        level: node.level,
+        frequency: node.frequency
+    };
+
+    for child in &node.children {
+        let character = match child.letter {
+            LetterState::Present(character) => character,
+            LetterState::Root => continue
+        };
+
+        let blanks_left = if counts.take(character) {
+            blanks
+        } else if blanks > 0 {
+            blanks - 1
+        } else {
+            continue;
+        };
+
+        let pruned_child = prune_below(child, counts, blanks_left);
+
+        if blanks_left == blanks {
+            counts.put_back(character);
+        }
+
+        if !pruned_child.children.is_empty() || matches!(pruned_child.state, NodeState::WordEnd) {
+            pruned.insert_child(pruned_child);
+        }
+    }
+
+    pruned
+}
+
+/*
+    A real letter gets used before a blank, since either way spells the
+    same word for the same points.
+ */
+fn add_anagrams_below(node: &LetterNode, counts: &mut LetterCounts, blanks: usize, word: &mut String, words: &mut Vec<String>) {
+    for child in &node.children {
+        let character = match child.letter {
+            LetterState::Present(character) => character,
+            LetterState::Root => continue
+        };
+
+        let blanks_left = if counts.take(character) {
+            blanks
+        } else if blanks > 0 {
+            blanks - 1
+        } else {
+            continue;
+        };
+
+        word.push(character);
+
+        if let NodeState::WordEnd = child.state {
+            words.push(word.clone());
+        }
+
+        add_anagrams_below(child, counts, blanks_left, word, words);
+
+        word.pop();
+        if blanks_left == blanks {
+            counts.put_back(character);
+        }
+    }
+}
+
+fn add_stats_below(node: &LetterNode, stats: &mut TreeStats) {
+    for child in &node.children {
+        stats.nodes += 1;
+
+        if child.children.is_empty() {
+            stats.leaves += 1;
+        }
+
+        if let NodeState::WordEnd = child.state {
+            stats.words += 1;
+
+            if stats.by_length.len() <= child.level {
+                stats.by_length.resize(child.level + 1, 0);
+            }
+            stats.by_length[child.level] += 1;
         }
 
-        write!(f, "{tree_str}")
+        add_stats_below(child, stats);
+    }
+}
+
+impl Lexicon for WordTree {
+    type Node<'a> = &'a LetterNode;
+
+    fn root(&self) -> &LetterNode {
+        &self.root
+    }
+
+    fn child<'a>(&'a self, node: &'a LetterNode, character: char) -> Option<&'a LetterNode> {
+        node.get_child_from_letter(character)
+    }
+
+    fn is_word_end<'a>(&'a self, node: &'a LetterNode) -> bool {
+        matches!(node.state, NodeState::WordEnd)
+    }
+
+    fn frequency<'a>(&'a self, node: &'a LetterNode) -> Option<u32> {
+        node.frequency
+    }
 
+    fn for_each_child<'a>(&'a self, node: &'a LetterNode, mut f: impl FnMut(char, &'a LetterNode)) {
+        for child in &node.children {
+            if let LetterState::Present(character) = child.letter {
+                f(character, child);
+            }
+        }
     }
 }
 
@@ -201,54 +791,119 @@ impl LetterNode {
         self.level
     }
 
+    pub fn frequency(&self) -> Option<u32> {
+        self.frequency
+    }
+
     /*
-        Runs through the given node's children and searches for the provided
-        character
+        Finds the provided character in the given node's children
 
         Takes a mutable reference to the node, and returns either a mutable
         reference to its child or None (no child with character found)
 
      */
     fn get_char_in_children(&mut self, character: char) -> Option<&mut LetterNode> {
+        match self.get_child_index(character) {
+            Some(index) => Some(&mut self.children[index]),
+            None => None
+        }
+    }
 
-        // For each child in the list of children
-        for child in &mut self.children{
-            // Match on the child's LetterState enum
-            match child.letter{
-                // If present, do some comparison
-                LetterState::Present(this_char) => { 
-                    if this_char == character{
-                        return Some(child);
+    pub fn get_child_from_letter(&self, character: char) -> Option<&LetterNode>{
+        match self.get_child_index(character) {
+            Some(index) => Some(&self.children[index]),
+            None => None
+        }
+    }
+
+    fn get_child_index(&self, character: char) -> Option<usize> {
+        match get_letter_slot(character) {
+            Some(slot) => {
+                if self.child_mask & (1 << slot) == 0 {
+                    return None;
+                }
+
+                // Number of slotted children before this one
+                Some((self.child_mask & ((1 << slot) - 1)).count_ones() as usize)
+            },
+            None => {
+                let slotted = self.child_mask.count_ones() as usize;
+
+                for (index, child) in self.children.iter().enumerate().skip(slotted) {
+                    if let LetterState::Present(letter) = child.letter {
+                        if letter == character {
+                            return Some(index);
+                        }
                     }
-                    continue
+                }
+
+                None
+            }
+        }
+    }
+
+    // Returns whether characters was a word below this node and got removed
+    fn remove_below(&mut self, characters: &[char]) -> bool {
+        let index = match self.get_child_index(characters[0]) {
+            Some(index) => index,
+            None => return false
+        };
+
+        let child = &mut self.children[index];
+
+        let removed = if characters.len() == 1 {
+            match child.state {
+                NodeState::WordEnd => {
+                    child.state = NodeState::WordMiddle;
+                    child.frequency = None;
+                    true
                 },
-                // Otherwise, keep going
-                LetterState::Root => continue
-            };
+                NodeState::WordMiddle => false
+            }
+        } else {
+            child.remove_below(&characters[1..])
+        };
+
+        // Nothing ends at or below the child anymore
+        if removed && child.children.is_empty() && matches!(child.state, NodeState::WordMiddle) {
+            self.remove_child(index);
         }
 
-        None
+        removed
     }
 
-    pub fn get_child_from_letter(&self, character: char) -> Option<&LetterNode>{
-        for child in &self.children {
-            match child.letter {
-                LetterState::Present(letter) => {
-                    if letter == character {
-                        return Some(&child);
-                    }
-                    continue
-                },
-                LetterState::Root => continue
+    fn remove_child(&mut self, index: usize) {
+        let child = self.children.remove(index);
+
+        if let LetterState::Present(character) = child.letter {
+            if let Some(slot) = get_letter_slot(character) {
+                self.child_mask &= !(1 << slot);
             }
         }
-        None
+    }
+
+    // Caller makes sure there isn't already a child with this letter
+    fn insert_child(&mut self, child: LetterNode) {
+        let character = match child.letter {
+            LetterState::Present(character) => character,
+            LetterState::Root => panic!("Root can't be a child.")
+        };
+
+        match get_letter_slot(character) {
+            Some(slot) => {
+                let index = (self.child_mask & ((1 << slot) - 1)).count_ones() as usize;
+                self.children.insert(index, child);
+                self.child_mask |= 1 << slot;
+            },
+            None => self.children.push(child)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::normalize::InvalidWords;
 
     #[test]
     fn test_words_in_tree_simple(){
@@ -261,6 +916,226 @@ mod tests {
         assert!(!tree.is_word_in_tree("obf"));
     }
 
+    #[test]
+    fn test_children_out_of_order(){
+        // Straight into the tree, since loading would skip don't and café
+        let mut tree = WordTree::new();
+        for word in ["zebra", "don't", "apple", "zeal", "café", "cab"] {
+            tree.add_word_to_tree(word);
+        }
+
+        assert!(tree.is_word_in_tree("zebra"));
+        assert!(tree.is_word_in_tree("zeal"));
+        assert!(tree.is_word_in_tree("apple"));
+        assert!(tree.is_word_in_tree("don't"));
+        assert!(tree.is_word_in_tree("café"));
+        assert!(tree.is_word_in_tree("cab"));
+        assert!(!tree.is_word_in_tree("don"));
+        assert!(!tree.is_word_in_tree("cafe"));
+    }
+
+    #[test]
+    fn test_binary_round_trip(){
+        let source = "apple\napples\napply\nbanana\ndon't\ncafé\ncab";
+        let mut tree = WordTree::new();
+        for word in source.lines() {
+            tree.add_word_to_tree(word);
+        }
+        let source_hash = dictionary_file::hash_source(source);
+
+        let bytes = tree.to_binary(source_hash).unwrap();
+        assert_eq!(dictionary_file::read_source_hash(&bytes), Ok(source_hash));
+
+        let loaded = WordTree::from_binary(&bytes).unwrap();
+        assert!(loaded.words().eq(tree.words()));
+        assert!(loaded.is_word_in_tree("café"));
+        assert!(!loaded.is_word_in_tree("appl"));
+
+        assert!(WordTree::from_binary(&bytes[..bytes.len() - 1]).is_err());
+        assert!(WordTree::from_binary(b"not a dictionary").is_err());
+    }
+
+    #[test]
+    fn test_normalized_loading(){
+        let input = "# fruit\r\nApple  \r\n\n  banana # yellow\ndon't\napple\nr2d2\ncafé\n";
+        let (tree, report) = WordTree::try_build_from_str(input, &NormalizePolicy::default()).unwrap();
+
+        assert!(tree.is_word_in_tree("apple"));
+        assert!(tree.is_word_in_tree("banana"));
+        assert!(!tree.is_word_in_tree("don't"));
+        assert!(!tree.is_word_in_tree("café"));
+        assert_eq!(report, LoadReport { lines: 8, accepted: 2, duplicates: 1, blank: 2, invalid: 3 });
+
+        let reject = NormalizePolicy::new(InvalidWords::Reject, true);
+        let error = WordTree::try_build_from_str(input, &reject).err().unwrap();
+        assert_eq!(error, "Line 5: \"don't\" has '\'', which isn't a letter");
+
+        assert!(WordTree::try_build_from_file("./data/dictionaries/missing.txt", &reject).is_err());
+    }
+
+    #[test]
+    fn test_empty_input(){
+        let (tree, report) = WordTree::try_build_from_str("", &NormalizePolicy::default()).unwrap();
+
+        assert_eq!(report, LoadReport::default());
+        assert!(!tree.is_word_in_tree("a"));
+        assert!(!tree.is_word_in_tree(""));
+    }
+
+    #[test]
+    fn test_words_and_stats(){
+        let tree = WordTree::build_from_str("zebra\napple\napples\ncab\nzeal\napple");
+
+        let words: Vec<String> = tree.words().collect();
+        assert_eq!(words, ["apple", "apples", "cab", "zeal", "zebra"]);
+
+        assert_eq!(tree.len(), 5);
+        assert!(tree.contains("apples"));
+        assert!(!tree.contains("appl"));
+        assert_eq!(tree.length_histogram(), [0, 0, 0, 1, 1, 2, 1]);
+
+        // a-p-p-l-e-s, c-a-b, z-e-a-l and the "bra" off "ze"
+        assert_eq!(tree.node_count(), 6 + 3 + 4 + 3);
+        assert_eq!(tree.stats().leaves, 4);
+
+        assert_eq!(format!("{tree}"), "5 words, 16 nodes (4 leaves)\n  3 letters: 1\n  4 letters: 1\n  5 letters: 2\n  6 letters: 1\n");
+        assert!(format!("{tree:#}").ends_with("zeal\nzebra\n"));
+
+        let empty = WordTree::new();
+        assert!(empty.is_empty());
+        assert_eq!(empty.words().count(), 0);
+        assert_eq!(format!("{empty}"), "0 words, 0 nodes (0 leaves)\n");
+    }
+
+    #[test]
+    fn test_prefix_and_pattern_queries(){
+        let tree = WordTree::build_from_str("quit\nquite\nquiet\nqueen\nquay\nabide\noxide\nsing\nsinging\nbringing\nwalking");
+
+        let qui: Vec<String> = tree.words_with_prefix("qui").collect();
+        assert_eq!(qui, ["quiet", "quit", "quite"]);
+        assert_eq!(tree.words_with_prefix("quit").collect::<Vec<String>>(), ["quit", "quite"]);
+        assert_eq!(tree.words_with_prefix("x").count(), 0);
+        assert_eq!(tree.words_with_prefix("").count(), tree.len());
+
+        assert_eq!(tree.matching("?x?de"), ["oxide"]);
+        assert_eq!(tree.matching("qu??"), ["quay", "quit"]);
+        assert_eq!(tree.matching("?????*ing"), ["bringing"]);
+        assert_eq!(tree.matching("????*ing"), ["bringing", "singing", "walking"]);
+        assert_eq!(tree.matching("*in*"), ["bringing", "sing", "singing", "walking"]);
+        assert_eq!(tree.matching("**"), tree.words().collect::<Vec<String>>());
+        assert!(tree.matching("").is_empty());
+        assert!(tree.matching("quit?").contains(&String::from("quite")));
+    }
+
+    #[test]
+    fn test_anagrams(){
+        let tree = WordTree::build_from_str("tea\neat\nate\neats\nseat\ntease\nzeta\nsteam");
+
+        let words = tree.anagrams("TEAS", 0);
+        assert_eq!(words, [
+            (String::from("eats"), 6),
+            (String::from("seat"), 6),
+            (String::from("ate"), 4),
+            (String::from("eat"), 4),
+            (String::from("tea"), 4)
+        ]);
+
+        // Only one e to go around, unless a blank makes up for it
+        assert!(!tree.anagrams("teas", 0).iter().any(|(word, _)| word == "tease"));
+        assert!(tree.anagrams("teas", 1).iter().any(|(word, _)| word == "tease"));
+
+        // The blank is the z and scores like one
+        assert_eq!(tree.anagrams("eta", 1)[0], (String::from("zeta"), 12));
+        assert_eq!(tree.anagrams("", 0), []);
+        assert_eq!(tree.anagrams("", 5).len(), 8);
+    }
+
+    #[test]
+    fn test_frequencies(){
+        let mut tree = WordTree::build_from_str("the\nthee\nthey\nzyzzyva");
+        let input = "# word\tcount\nThe\t23135851162\nthey\t1000\nzyzzyva\t3\nunlisted\t50\n\n";
+
+        assert_eq!(tree.load_frequencies_from_str(input), Ok(3));
+        assert_eq!(tree.word_frequency("the"), Some(u32::MAX));
+        assert_eq!(tree.word_frequency("they"), Some(1000));
+        assert_eq!(tree.word_frequency("thee"), None);
+        assert_eq!(tree.word_frequency("th"), None);
+
+        assert!(tree.load_frequencies_from_str("they 1000").is_err());
+        assert!(tree.load_frequencies_from_str("they\tlots").is_err());
+        assert!(tree.load_frequencies_from_str("\t1000").is_err());
+
+        // Read like the word list: comments and case the same, bad words up to the policy
+        assert_eq!(tree.load_frequencies_from_str("  THEE\t7 # note\nth3e\t9"), Ok(1));
+        assert_eq!(tree.word_frequency("thee"), Some(7));
+        assert!(tree.load_frequencies_with("th3e\t9", &NormalizePolicy::new(InvalidWords::Reject, true)).is_err());
+
+        // Saved with the words
+        let loaded = WordTree::from_binary(&tree.to_binary(0).unwrap()).unwrap();
+        assert_eq!(loaded.word_frequency("they"), Some(1000));
+        assert_eq!(loaded.word_frequency("thee"), Some(7));
+        assert_eq!(loaded.word_frequency("zyzzyva"), Some(3));
+
+        // Gone with the word, and not back if it's added again
+        assert!(tree.remove_word("they"));
+        assert!(tree.add_word_to_tree("they"));
+        assert_eq!(tree.word_frequency("they"), None);
+    }
+
+    #[test]
+    fn test_pruned_for_letters(){
+        let tree = WordTree::build_from_str("tea\neat\nate\neats\nseat\ntease\nzeta\nsteam");
+
+        let pruned = tree.pruned_for_letters("teas", 0);
+        assert_eq!(pruned.words().collect::<Vec<String>>(), ["ate", "eat", "eats", "seat", "tea"]);
+        assert_eq!(pruned.node_count(), 3 + 4 + 4 + 3);
+
+        let anagrams: Vec<String> = tree.anagrams("teas", 1).into_iter().map(|(word, _)| word).collect();
+        let mut pruned: Vec<String> = tree.pruned_for_letters("teas", 1).words().collect();
+        pruned.sort_by_key(|word| anagrams.iter().position(|anagram| anagram == word));
+        assert_eq!(pruned, anagrams);
+
+        assert!(tree.pruned_for_letters("", 0).is_empty());
+    }
+
+    #[test]
+    fn test_insert_and_remove(){
+        let mut tree = WordTree::build_from_str("apples\ncab");
+
+        // A prefix of a word already in the tree
+        assert!(!tree.contains("apple"));
+        assert!(tree.add_word_to_tree("apple"));
+        assert!(tree.contains("apple"));
+        assert!(!tree.add_word_to_tree("apple"));
+        assert!(!tree.add_word_to_tree(""));
+
+        assert!(tree.remove_word("apples"));
+        assert!(!tree.remove_word("apples"));
+        assert!(!tree.remove_word("appl"));
+        assert!(tree.contains("apple"));
+        assert_eq!(tree.node_count(), 5 + 3);
+
+        assert!(tree.remove_word("apple"));
+        assert!(tree.remove_word("cab"));
+        assert!(tree.is_empty());
+        assert_eq!(tree.node_count(), 0);
+        assert_eq!(tree.get_root_node().child_mask, 0);
+
+        // The mask has to be right for the letters to be found again
+        assert!(tree.add_word_to_tree("cab"));
+        assert!(tree.contains("cab"));
+    }
+
+    #[cfg(feature = "embedded-dictionary")]
+    #[test]
+    fn test_default_english(){
+        let tree = WordTree::default_english();
+
+        assert!(tree.is_word_in_tree("finch"));
+        assert!(tree.is_word_in_tree("xylophone"));
+        assert!(!tree.is_word_in_tree("yotemyscrote"));
+    }
+
     #[test]
     fn test_words_big(){
         let tree =  WordTree::build_from_file("./data/dictionaries/dictionary.txt");